@@ -1,12 +1,64 @@
+pub mod attrs;
+pub mod bee;
+pub mod cell;
+pub mod colmena_configurations;
 pub mod configurations;
+pub mod darwin_configurations;
+pub mod devshells;
+pub mod disko_configurations;
+pub mod flake;
+pub mod group_template;
+pub mod hardware_profiles;
+pub mod home_configurations;
+pub mod host_naming;
 pub mod import;
 pub mod inherit;
+pub mod inline;
+pub mod parser;
+pub mod region;
+pub mod registry;
+pub mod tree;
 pub mod variable;
 
+pub use crate::hive::bee::Bee;
+pub use crate::hive::colmena_configurations::ColmenaConfiguration;
+pub use crate::hive::colmena_configurations::ColmenaConfigurations;
+pub use crate::hive::colmena_configurations::Deployment;
+pub use crate::hive::configurations::Block;
 pub use crate::hive::configurations::ConfigurationType;
 pub use crate::hive::configurations::Configurations;
+pub use crate::hive::configurations::ConfigurationsBuilder;
 pub use crate::hive::configurations::NixosConfigurations;
+pub use crate::hive::configurations::NixosConfigurationsBuilder;
+pub use crate::hive::configurations::Overrides;
+pub use crate::hive::darwin_configurations::DarwinConfigurations;
+pub use crate::hive::devshells::Devshell;
+pub use crate::hive::devshells::Devshells;
+pub use crate::hive::disko_configurations::Content;
+pub use crate::hive::disko_configurations::Dataset;
+pub use crate::hive::disko_configurations::Disk;
+pub use crate::hive::disko_configurations::DiskoConfiguration;
+pub use crate::hive::disko_configurations::DiskoConfigurations;
+pub use crate::hive::disko_configurations::LogicalVolume;
+pub use crate::hive::disko_configurations::Mdadm;
+pub use crate::hive::disko_configurations::Partition;
+pub use crate::hive::disko_configurations::Subvolume;
+pub use crate::hive::disko_configurations::VolumeGroup;
+pub use crate::hive::disko_configurations::Zpool;
+pub use crate::hive::group_template::BlockGroup;
+pub use crate::hive::group_template::GroupTemplate;
+pub use crate::hive::group_template::TemplateBlock;
+pub use crate::hive::hardware_profiles::FileSystem;
+pub use crate::hive::hardware_profiles::HardwareProfile;
+pub use crate::hive::hardware_profiles::HardwareProfiles;
+pub use crate::hive::hardware_profiles::Microcode;
+pub use crate::hive::home_configurations::HomeConfiguration;
+pub use crate::hive::home_configurations::HomeConfigurations;
+pub use crate::hive::host_naming::HostNaming;
+pub use crate::hive::host_naming::HostPattern;
 pub use crate::hive::import::Import;
 pub use crate::hive::import::Imports;
 pub use crate::hive::inherit::Inherit;
+pub use crate::hive::inline::Inline;
+pub use crate::hive::region::Region;
 pub use crate::hive::variable::Variable;
@@ -0,0 +1,196 @@
+use crate::nix::NixValue;
+use genco::prelude::*;
+use genco::tokens::from_fn;
+
+/// Words the Nix lexer never reads as an identifier.
+const KEYWORDS: [&str; 10] = [
+    "assert", "else", "if", "in", "inherit", "let", "or", "rec", "then", "with",
+];
+
+fn is_identifier_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '\'')
+}
+
+/// Whether `name` can be written as a bare Nix identifier.
+///
+/// ```
+/// use honey::hive::attrs::is_identifier;
+///
+/// assert!(is_identifier("machine00"));
+/// assert!(is_identifier("nixos-23-05"));
+/// assert!(!is_identifier("01-db"));
+/// assert!(!is_identifier("web.prod"));
+/// assert!(!is_identifier("inherit"));
+/// ```
+pub fn is_identifier(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(is_identifier_char)
+        && !KEYWORDS.contains(&name)
+}
+
+/// A Nix string literal holding `value`. Unlike `quoted`, which escapes the
+/// C way, `${` is escaped and non-ASCII characters are kept as they are.
+///
+/// ```
+/// use genco::prelude::*;
+/// use honey::hive::attrs::string;
+///
+/// let toks: nix::Tokens = string("${pkgs} \"quoted\"\n");
+///
+/// assert_eq!(r#""\${pkgs} \"quoted\"\n""#, toks.to_string()?);
+/// # Ok::<_, genco::fmt::Error>(())
+/// ```
+pub fn string(value: &str) -> nix::Tokens {
+    NixValue::from(value).into()
+}
+
+/// Attribute name, quoted unless it is a plain identifier such as `ESP`.
+///
+/// ```
+/// use genco::prelude::*;
+/// use honey::hive::attrs::attr_name;
+///
+/// assert_eq!("machine00", attr_name("machine00").to_string()?);
+/// assert_eq!("\"web.prod\"", attr_name("web.prod").to_string()?);
+/// assert_eq!("\"01-db\"", attr_name("01-db").to_string()?);
+/// assert_eq!("\"inherit\"", attr_name("inherit").to_string()?);
+/// assert_eq!("\"\"", attr_name("").to_string()?);
+/// assert_eq!(r#""\${x} \"y\"""#, attr_name("${x} \"y\"").to_string()?);
+/// assert_eq!("\"héllo\"", attr_name("héllo").to_string()?);
+/// # Ok::<_, genco::fmt::Error>(())
+/// ```
+///
+/// Any two UTF-8 names read back as the same attribute path:
+///
+/// ```
+/// use genco::prelude::*;
+/// use honey::hive::attrs::attr_name;
+/// use honey::nix::{Binding, Expr};
+/// use proptest::prelude::*;
+/// use proptest::test_runner::TestRunner;
+///
+/// let round_trip = |a: &str, b: &str| {
+///     let toks: nix::Tokens = quote!({ $(attr_name(a)).$(attr_name(b)) = null; });
+///     let input = toks.to_string().unwrap();
+///     let ok = match honey::nix::parse(&input) {
+///         Ok(Expr::AttrSet { bindings, .. }) => match bindings.as_slice() {
+///             [Binding::Attr { path, .. }] => {
+///                 path.iter().map(|name| name.as_str()).collect::<Vec<_>>() == [Some(a), Some(b)]
+///             }
+///             _ => false,
+///         },
+///         _ => false,
+///     };
+///     (input, ok)
+/// };
+///
+/// for name in ["machine00", "web.prod", "01-db", "my host", "it's", "\"quoted\"", "${x}", "héllo", "ü.日本", ""] {
+///     let (input, ok) = round_trip(name, "x");
+///     assert!(ok, "{}", input);
+/// }
+///
+/// TestRunner::default().run(&(any::<String>(), any::<String>()), |(a, b)| {
+///     let (input, ok) = round_trip(&a, &b);
+///     prop_assert!(ok, "{}", input);
+///     Ok(())
+/// }).unwrap();
+/// ```
+pub fn attr_name(name: &str) -> nix::Tokens {
+    if is_identifier(name) {
+        quote!($name)
+    } else {
+        string(name)
+    }
+}
+
+/// A `let` binding name made of `prefix`, a dash and `name`, such as
+/// `bee-machine00`. Characters an identifier can not hold, and `'` itself,
+/// are written as `'<hex>'`, so distinct names never share a binding.
+///
+/// ```
+/// use honey::hive::attrs::binding_name;
+///
+/// assert_eq!("bee-machine00", binding_name("bee", "machine00"));
+/// assert_eq!("bee-01-db", binding_name("bee", "01-db"));
+/// assert_eq!("bee-web'2e'prod", binding_name("bee", "web.prod"));
+/// assert_eq!("bee-my'20'host", binding_name("bee", "my host"));
+/// assert_eq!("bee-it'27's", binding_name("bee", "it's"));
+/// assert_eq!("bee-h'e9'llo", binding_name("bee", "héllo"));
+/// assert_eq!("bee-", binding_name("bee", ""));
+/// ```
+///
+/// Any UTF-8 name gives a binding the parser reads back, and distinct names
+/// give distinct bindings:
+///
+/// ```
+/// use honey::hive::attrs::binding_name;
+/// use honey::nix::{Binding, Expr};
+/// use proptest::prelude::*;
+/// use proptest::test_runner::TestRunner;
+///
+/// let round_trip = |name: &str| {
+///     let binding = binding_name("bee", name);
+///     let input = format!("let {} = null; in {}", binding, binding);
+///     let ok = match honey::nix::parse(&input) {
+///         Ok(Expr::Let { bindings, body }) => {
+///             matches!(bindings.as_slice(), [Binding::Attr { path, .. }] if path.len() == 1 && path[0].as_str() == Some(binding.as_str()))
+///                 && *body == Expr::Ident(binding.clone())
+///         }
+///         _ => false,
+///     };
+///     (input, ok)
+/// };
+///
+/// for name in ["machine00", "web.prod", "01-db", "my host", "it's", "\"quoted\"", "${x}", "héllo", "ü.日本", ""] {
+///     let (input, ok) = round_trip(name);
+///     assert!(ok, "{}", input);
+/// }
+///
+/// TestRunner::default().run(&(any::<String>(), any::<String>()), |(a, b)| {
+///     let (input, ok) = round_trip(&a);
+///     prop_assert!(ok, "{}", input);
+///     prop_assert_eq!(a == b, binding_name("bee", &a) == binding_name("bee", &b));
+///     Ok(())
+/// }).unwrap();
+/// ```
+pub fn binding_name(prefix: &str, name: &str) -> String {
+    let mut binding = format!("{}-", prefix);
+    for c in name.chars() {
+        if c != '\'' && is_identifier_char(c) {
+            binding.push(c);
+        } else {
+            binding.push_str(&format!("'{:x}'", c as u32));
+        }
+    }
+    binding
+}
+
+/// `{ name = value; ... }` for named values.
+pub(crate) fn attrs<T>(items: Vec<(String, T)>) -> impl FormatInto<Nix>
+where
+    T: FormatInto<Nix>,
+{
+    from_fn(move |tokens| {
+        tokens.append("{");
+        tokens.indent();
+        for (name, value) in items {
+            quote_in!(*tokens => $(attr_name(&name)) = $value;);
+            tokens.push();
+        }
+        tokens.unindent();
+        tokens.append("}");
+    })
+}
+
+pub(crate) fn strings(items: Vec<String>) -> impl FormatInto<Nix> {
+    from_fn(move |tokens| {
+        tokens.append("[");
+        tokens.indent();
+        for item in items {
+            tokens.append(string(&item));
+            tokens.push();
+        }
+        tokens.unindent();
+        tokens.append("]");
+    })
+}
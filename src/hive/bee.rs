@@ -0,0 +1,217 @@
+use crate::hive::*;
+use crate::nix::NixValue;
+use genco::prelude::*;
+use std::fmt;
+
+/// Error returned when bee attributes can not be combined.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Error {
+    /// Two attributes that exclude each other are both set.
+    Conflict(&'static str, &'static str),
+    /// `system` is not of the form `<arch>-<os>`.
+    InvalidSystem(String),
+    /// An attribute does not fit the system, e.g. `darwin` on Linux.
+    System(&'static str, String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Conflict(a, b) => write!(f, "`bee.{}` and `bee.{}` exclude each other", a, b),
+            Error::InvalidSystem(system) => {
+                write!(f, "invalid system `{}`, expected `<arch>-<os>`", system)
+            }
+            Error::System(attribute, system) => {
+                write!(f, "`bee.{}` does not fit system `{}`", attribute, system)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// The `bee` attributes hive reads from a configuration.
+///
+/// ```
+/// use genco::prelude::*;
+/// use honey::hive::*;
+///
+/// let bee = Bee::new("x86_64-linux", Inherit::nixpkgs())
+///     .home(Inherit::home_manager())
+///     .wsl(Inherit::new("inputs", "nixos-wsl"))
+///     .build("bee")?;
+///
+/// let toks = quote!($bee);
+///
+/// assert_eq!(
+///     vec![
+///         "{",
+///         "    inputs,",
+///         "    ...",
+///         "}:",
+///         "",
+///         "let",
+///         "    inherit (inputs) home-manager;",
+///         "    inherit (inputs) nixos-wsl;",
+///         "    inherit (inputs) nixpkgs;",
+///         "    bee = {",
+///         "        bee = {",
+///         "            home = home-manager;",
+///         "            pkgs = nixpkgs;",
+///         "            system = \"x86_64-linux\";",
+///         "            wsl = nixos-wsl;",
+///         "        };",
+///         "    };",
+///         "in",
+///         "",
+///         "bee"
+///     ],
+///     toks.to_file_vec()?
+/// );
+/// # Ok::<_, Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Clone)]
+pub struct Bee {
+    pub colmena: Option<NixValue>,
+    pub darwin: Option<NixValue>,
+    pub home: Option<NixValue>,
+    pub pkgs: NixValue,
+    pub system: String,
+    pub wsl: Option<NixValue>,
+}
+
+impl Bee {
+    pub fn new<N>(system: &str, pkgs: N) -> Self
+    where
+        N: Into<NixValue>,
+    {
+        Self {
+            colmena: None,
+            darwin: None,
+            home: None,
+            pkgs: pkgs.into(),
+            system: String::from(system),
+            wsl: None,
+        }
+    }
+
+    pub fn colmena<T>(mut self, colmena: T) -> Self
+    where
+        T: Into<NixValue>,
+    {
+        self.colmena = Some(colmena.into());
+        self
+    }
+
+    pub fn darwin<T>(mut self, darwin: T) -> Self
+    where
+        T: Into<NixValue>,
+    {
+        self.darwin = Some(darwin.into());
+        self
+    }
+
+    pub fn home<T>(mut self, home: T) -> Self
+    where
+        T: Into<NixValue>,
+    {
+        self.home = Some(home.into());
+        self
+    }
+
+    pub fn wsl<T>(mut self, wsl: T) -> Self
+    where
+        T: Into<NixValue>,
+    {
+        self.wsl = Some(wsl.into());
+        self
+    }
+
+    /// Check that the attributes can be combined: nix-darwin needs a darwin
+    /// system, NixOS-WSL and colmena need a Linux one.
+    ///
+    /// ```
+    /// use honey::hive::*;
+    /// use honey::hive::bee::Error;
+    ///
+    /// let bee = Bee::new("x86_64-linux", Inherit::nixpkgs()).darwin(Inherit::new("inputs", "darwin"));
+    /// assert_eq!(Err(Error::System("darwin", String::from("x86_64-linux"))), bee.validate());
+    ///
+    /// let bee = Bee::new("aarch64-darwin", Inherit::nixpkgs())
+    ///     .darwin(Inherit::new("inputs", "darwin"))
+    ///     .colmena(Inherit::new("inputs", "colmena"));
+    /// assert_eq!(Err(Error::Conflict("colmena", "darwin")), bee.validate());
+    ///
+    /// let bee = Bee::new("linux", Inherit::nixpkgs());
+    /// assert_eq!(
+    ///     "invalid system `linux`, expected `<arch>-<os>`",
+    ///     bee.validate().unwrap_err().to_string()
+    /// );
+    /// ```
+    pub fn validate(&self) -> Result<(), Error> {
+        let os = match self.system.split_once('-') {
+            Some((arch, os)) if !arch.is_empty() && !os.is_empty() => os,
+            _ => return Err(Error::InvalidSystem(self.system.clone())),
+        };
+        if self.darwin.is_some() {
+            if self.colmena.is_some() {
+                return Err(Error::Conflict("colmena", "darwin"));
+            }
+            if self.wsl.is_some() {
+                return Err(Error::Conflict("darwin", "wsl"));
+            }
+            if os != "darwin" {
+                return Err(Error::System("darwin", self.system.clone()));
+            }
+        }
+        if self.wsl.is_some() && os != "linux" {
+            return Err(Error::System("wsl", self.system.clone()));
+        }
+        if self.colmena.is_some() && os != "linux" {
+            return Err(Error::System("colmena", self.system.clone()));
+        }
+        Ok(())
+    }
+
+    /// Validate the bee and bind it to `name` in the `let` block.
+    pub fn build(self, name: &str) -> Result<Variable, Error> {
+        self.validate()?;
+        Ok(self.variable(name))
+    }
+
+    /// Bind the bee to `name` in the `let` block without validating it.
+    pub fn variable(self, name: &str) -> Variable {
+        Variable {
+            name: name.to_string(),
+            value: self.into(),
+        }
+    }
+}
+
+impl From<Bee> for NixValue {
+    fn from(bee: Bee) -> Self {
+        let mut attrs = Vec::new();
+        let mut set = |name: &str, value: NixValue| attrs.push((vec![name.to_string()], value));
+        if let Some(colmena) = bee.colmena {
+            set("colmena", colmena);
+        }
+        if let Some(darwin) = bee.darwin {
+            set("darwin", darwin);
+        }
+        if let Some(home) = bee.home {
+            set("home", home);
+        }
+        set("pkgs", bee.pkgs);
+        set("system", bee.system.into());
+        if let Some(wsl) = bee.wsl {
+            set("wsl", wsl);
+        }
+        NixValue::AttrSet(vec![(vec![String::from("bee")], NixValue::AttrSet(attrs))])
+    }
+}
+
+impl FormatInto<Nix> for Bee {
+    fn format_into(self, tokens: &mut Tokens<Nix>) {
+        tokens.append(NixValue::from(self));
+    }
+}
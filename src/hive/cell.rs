@@ -0,0 +1,257 @@
+use crate::hive::flake::Inputs;
+use crate::hive::inherit::{self, Inherited, Inherits};
+use crate::hive::region;
+use genco::fmt;
+use genco::prelude::*;
+use std::collections::BTreeSet;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// A rendered cell block, ready to be written to disk.
+pub struct File {
+    pub path: PathBuf,
+    pub contents: String,
+}
+
+pub struct Block {
+    /// Block name, e.g. `nixosConfigurations`.
+    pub name: String,
+    pub tokens: nix::Tokens,
+    /// Flake inputs the block refers to.
+    pub inputs: BTreeSet<String>,
+    /// Inherits the block writes, for `Cell::merge_inherits`.
+    pub inherited: Inherited,
+}
+
+/// The blocks of one hive cell, written to `cells/<name>/<block>.nix`.
+pub struct Cell {
+    pub name: String,
+    pub blocks: Vec<Block>,
+}
+
+impl Cell {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: String::from(name),
+            blocks: Vec::new(),
+        }
+    }
+
+    /// Add a block to the cell.
+    ///
+    /// ```
+    /// use honey::hive::*;
+    /// use honey::hive::cell::Cell;
+    ///
+    /// let mut cell = Cell::new("hosts");
+    /// cell.block("nixosConfigurations", NixosConfigurations::new("machine1"));
+    ///
+    /// assert_eq!("nixosConfigurations", cell.blocks[0].name);
+    /// ```
+    pub fn block<T>(&mut self, name: &str, block: T)
+    where
+        T: FormatInto<Nix> + Inputs + Inherits,
+    {
+        let mut inputs = BTreeSet::new();
+        block.inputs(&mut inputs);
+        let inherited = Inherited::of(&block);
+        self.blocks.push(Block {
+            name: String::from(name),
+            tokens: quote!($block),
+            inputs,
+            inherited,
+        })
+    }
+
+    /// Directory of the cell below the hive `root`.
+    pub fn path(&self, root: &Path) -> PathBuf {
+        root.join("cells").join(&self.name)
+    }
+
+    /// Render every block of the cell below the `header`. Scoped files leave
+    /// out the `{ cell, inputs, ... }:` argument pattern.
+    ///
+    /// ```
+    /// use honey::hive::*;
+    /// use honey::hive::cell::{self, Cell};
+    /// use std::path::Path;
+    ///
+    /// let mut cell = Cell::new("hosts");
+    /// cell.block("nixosModules", NixosConfigurations(vec![
+    ///     Configurations::new("machine1", vec![]),
+    /// ]));
+    ///
+    /// let files = cell.files(Path::new("hive"), true)?;
+    ///
+    /// assert_eq!(Path::new("hive/cells/hosts/nixosModules.nix"), files[0].path);
+    /// assert_eq!(
+    ///     format!("{}{{\n    machine1 = {{\n        imports = [];\n    }};\n}}\n", cell::header()),
+    ///     files[0].contents
+    /// );
+    /// # Ok::<_, genco::fmt::Error>(())
+    /// ```
+    pub fn files(&self, root: &Path, scoped: bool) -> fmt::Result<Vec<File>> {
+        let directory = self.path(root);
+        let mut files = Vec::new();
+        for block in &self.blocks {
+            let contents = render(&block.tokens, scoped)?;
+            files.push(File {
+                path: directory.join(format!("{}.nix", block.name)),
+                contents: header1(region::any(&contents)) + &contents,
+            });
+        }
+        Ok(files)
+    }
+
+    /// Merge the inherits of every block by source, see `inherit::merge`.
+    pub fn merge_inherits(&mut self) {
+        for block in &mut self.blocks {
+            block.tokens = inherit::merge(&block.tokens, &block.inherited);
+        }
+    }
+}
+
+/// First line of every generated file, naming the honey version that wrote
+/// it.
+///
+/// ```
+/// use honey::hive::cell;
+///
+/// assert!(cell::header().starts_with("# Generated by honey 0."));
+/// ```
+pub fn header() -> String {
+    header1(false)
+}
+
+/// Like `header`, for a file that has `regions` also telling that only
+/// those are to be edited.
+///
+/// ```
+/// use honey::hive::cell;
+///
+/// assert!(cell::header1(true).ends_with(", edit only between `# honey:begin` and `# honey:end`.\n"));
+/// ```
+pub fn header1(regions: bool) -> String {
+    if regions {
+        format!(
+            "# Generated by honey {}, edit only between `# honey:begin` and `# honey:end`.\n",
+            env!("CARGO_PKG_VERSION")
+        )
+    } else {
+        format!("# Generated by honey {}.\n", env!("CARGO_PKG_VERSION"))
+    }
+}
+
+/// Render tokens as a complete Nix file.
+pub fn render(tokens: &nix::Tokens, scoped: bool) -> fmt::Result<String> {
+    let mut w = fmt::FmtWriter::new(String::new());
+    let fmt = fmt::Config::from_lang::<Nix>();
+    let config = nix::Config::default().with_scoped(scoped);
+    tokens.format_file(&mut w.as_formatter(&fmt), &config)?;
+    Ok(w.into_inner())
+}
+
+/// Write files, creating parent directories as needed. Unless `overwrite` is
+/// set nothing is written when any of the files already exists.
+pub fn write(files: &[File], overwrite: bool) -> io::Result<()> {
+    if !overwrite {
+        let existing: Vec<String> = files
+            .iter()
+            .filter(|file| file.path.exists())
+            .map(|file| file.path.display().to_string())
+            .collect();
+        if !existing.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("refusing to overwrite {}", existing.join(", ")),
+            ));
+        }
+    }
+    for file in files {
+        if let Some(parent) = file.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&file.path, &file.contents)?;
+    }
+    Ok(())
+}
+
+/// A unified diff of every file whose contents on disk differ from the
+/// generated ones, an empty list when all are up to date. Missing files diff
+/// against `/dev/null`.
+///
+/// ```
+/// use genco::prelude::*;
+/// use honey::hive::*;
+/// use honey::hive::cell::{self, Cell};
+///
+/// let root = std::env::temp_dir().join("honey-diff-doctest");
+/// let _ = std::fs::remove_dir_all(&root);
+///
+/// let mut cell = Cell::new("hosts");
+/// cell.block("nixosConfigurations", NixosConfigurations::new1("machine", 1, None, "nixos-23-05", "x86_64-linux")?);
+/// let files = cell.files(&root, false)?;
+///
+/// assert!(cell::diff(&files)?[0].starts_with("--- /dev/null\n"));
+///
+/// cell::write(&files, false)?;
+/// assert!(cell::diff(&files)?.is_empty());
+///
+/// let edited = files[0].contents.replace("x86_64-linux", "aarch64-linux");
+/// std::fs::write(&files[0].path, edited)?;
+///
+/// let path = files[0].path.display();
+/// let diff = cell::diff(&files)?;
+/// assert!(diff[0].starts_with(&format!("--- {}\n+++ {}\n@@", path, path)));
+/// assert!(diff[0].contains("\n-            system = \"aarch64-linux\";\n+            system = \"x86_64-linux\";\n"));
+/// # std::fs::remove_dir_all(&root)?;
+/// # Ok::<_, Box<dyn std::error::Error>>(())
+/// ```
+///
+/// Blocks filled in by hand, such as `nixosModules`, are only edited within
+/// their regions. With the regions kept from disk by
+/// `region::preserve_files`, a filled-in stub is up to date:
+///
+/// ```
+/// use honey::hive::*;
+/// use honey::hive::cell::{self, Cell};
+/// use honey::hive::region;
+///
+/// let root = std::env::temp_dir().join("honey-diff-stub-doctest");
+/// let _ = std::fs::remove_dir_all(&root);
+///
+/// let mut cell = Cell::new("hosts");
+/// cell.block("nixosModules", NixosConfigurations::stubs(&[String::from("machine00")]));
+/// let files = cell.files(&root, false)?;
+/// cell::write(&files, false)?;
+///
+/// let filled = files[0].contents.replace(
+///     "# honey:begin machine00\n",
+///     "# honey:begin machine00\n            ./machine00.nix\n",
+/// );
+/// assert_ne!(files[0].contents, filled);
+/// std::fs::write(&files[0].path, filled)?;
+///
+/// let mut files = cell.files(&root, false)?;
+/// region::preserve_files(&mut files)?;
+/// assert!(cell::diff(&files)?.is_empty());
+/// # std::fs::remove_dir_all(&root)?;
+/// # Ok::<_, Box<dyn std::error::Error>>(())
+/// ```
+pub fn diff(files: &[File]) -> io::Result<Vec<String>> {
+    let mut diffs = Vec::new();
+    for file in files {
+        let path = file.path.display().to_string();
+        let (old, old_path) = match std::fs::read_to_string(&file.path) {
+            Ok(old) => (old, path.as_str()),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => (String::new(), "/dev/null"),
+            Err(error) => return Err(error),
+        };
+        if old == file.contents {
+            continue;
+        }
+        let diff = similar::TextDiff::from_lines(&old, &file.contents);
+        diffs.push(diff.unified_diff().header(old_path, &path).to_string());
+    }
+    Ok(diffs)
+}
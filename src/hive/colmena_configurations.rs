@@ -0,0 +1,214 @@
+use crate::hive::attrs::attr_name;
+use crate::hive::flake::Inputs;
+use crate::hive::inherit::{Inherited, Inherits};
+use crate::hive::*;
+use genco::prelude::*;
+use serde::Deserialize;
+use std::collections::BTreeSet;
+
+/// Colmena `deployment.*` settings of one host. Unset values are left out, so
+/// colmena falls back to its own defaults, e.g. the host name as target.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Deployment {
+    pub target_host: Option<String>,
+    pub target_user: Option<String>,
+    pub target_port: Option<u16>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub allow_local_deployment: Option<bool>,
+    pub build_on_target: Option<bool>,
+}
+
+impl Deployment {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+impl FormatInto<Nix> for Deployment {
+    /// ```
+    /// use genco::prelude::*;
+    /// use honey::hive::*;
+    ///
+    /// let deployment = Deployment {
+    ///     target_host: Some(String::from("10.0.0.1")),
+    ///     target_port: Some(2222),
+    ///     tags: vec![String::from("web")],
+    ///     build_on_target: Some(true),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let toks = quote!($deployment);
+    ///
+    /// assert_eq!(
+    ///     vec![
+    ///         "{",
+    ///         "    ...",
+    ///         "}:",
+    ///         "",
+    ///         "{",
+    ///         "    buildOnTarget = true;",
+    ///         "    tags = [",
+    ///         "        \"web\"",
+    ///         "    ];",
+    ///         "    targetHost = \"10.0.0.1\";",
+    ///         "    targetPort = 2222;",
+    ///         "}",
+    ///     ],
+    ///     toks.to_file_vec()?
+    /// );
+    /// # Ok::<_, genco::fmt::Error>(())
+    /// ```
+    fn format_into(self, tokens: &mut Tokens<Nix>) {
+        tokens.append("{");
+        tokens.indent();
+        if let Some(allow_local_deployment) = self.allow_local_deployment {
+            quote_in!(*tokens => allowLocalDeployment = $(allow_local_deployment.to_string()););
+            tokens.push();
+        }
+        if let Some(build_on_target) = self.build_on_target {
+            quote_in!(*tokens => buildOnTarget = $(build_on_target.to_string()););
+            tokens.push();
+        }
+        if !self.tags.is_empty() {
+            tokens.append("tags = [");
+            tokens.indent();
+            for tag in self.tags {
+                tokens.append(quoted(tag));
+                tokens.push();
+            }
+            tokens.unindent();
+            tokens.append("];");
+            tokens.push();
+        }
+        if let Some(target_host) = self.target_host {
+            quote_in!(*tokens => targetHost = $(quoted(target_host)););
+            tokens.push();
+        }
+        if let Some(target_port) = self.target_port {
+            quote_in!(*tokens => targetPort = $(target_port.to_string()););
+            tokens.push();
+        }
+        if let Some(target_user) = self.target_user {
+            quote_in!(*tokens => targetUser = $(quoted(target_user)););
+            tokens.push();
+        }
+        tokens.unindent();
+        tokens.append("}");
+    }
+}
+
+pub struct ColmenaConfiguration {
+    pub deployment: Deployment,
+    /// Name do **not** included in quote.
+    pub name: String,
+}
+
+impl FormatInto<Nix> for ColmenaConfiguration {
+    fn format_into(self, tokens: &mut Tokens<Nix>) {
+        let imports = Imports(vec![Import::cell_nixos_configurations(&self.name)]);
+        quote_in! { *tokens =>
+            {
+                imports = $(imports);
+                $(if !self.deployment.is_empty() {
+                    deployment = $(self.deployment);
+                })
+            }
+        }
+    }
+}
+
+pub struct ColmenaConfigurations(pub Vec<ColmenaConfiguration>);
+
+impl ColmenaConfigurations {
+    /// One entry per host of `nixos_configurations`, each importing the host
+    /// from `cell.nixosConfigurations`, so the two blocks can not drift apart.
+    ///
+    /// ```
+    /// use genco::prelude::*;
+    /// use honey::hive::*;
+    ///
+    /// let nixos_configurations = NixosConfigurations::new1("machine", 2, None, "nixos-23-05", "x86_64-linux")?;
+    ///
+    /// let colmena_configurations = ColmenaConfigurations::new(&nixos_configurations, |name| Deployment {
+    ///     target_host: Some(format!("{}.example.org", name)),
+    ///     target_user: Some(String::from("deploy")),
+    ///     allow_local_deployment: Some(name == "machine00"),
+    ///     ..Default::default()
+    /// });
+    ///
+    /// let toks = quote!($colmena_configurations);
+    ///
+    /// assert_eq!(
+    ///     vec![
+    ///         "{",
+    ///         "    cell,",
+    ///         "    ...",
+    ///         "}:",
+    ///         "",
+    ///         "{",
+    ///         "    machine00 = {",
+    ///         "        imports = [",
+    ///         "            cell.nixosConfigurations.machine00",
+    ///         "        ];",
+    ///         "        deployment = {",
+    ///         "            allowLocalDeployment = true;",
+    ///         "            targetHost = \"machine00.example.org\";",
+    ///         "            targetUser = \"deploy\";",
+    ///         "        };",
+    ///         "    };",
+    ///         "    machine01 = {",
+    ///         "        imports = [",
+    ///         "            cell.nixosConfigurations.machine01",
+    ///         "        ];",
+    ///         "        deployment = {",
+    ///         "            allowLocalDeployment = false;",
+    ///         "            targetHost = \"machine01.example.org\";",
+    ///         "            targetUser = \"deploy\";",
+    ///         "        };",
+    ///         "    };",
+    ///         "}",
+    ///     ],
+    ///     toks.to_file_vec()?
+    /// );
+    /// # Ok::<_, Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn new<F>(nixos_configurations: &NixosConfigurations, mut deployment: F) -> Self
+    where
+        F: FnMut(&str) -> Deployment,
+    {
+        Self(
+            nixos_configurations
+                .0
+                .iter()
+                .map(|configurations| ColmenaConfiguration {
+                    deployment: deployment(&configurations.name),
+                    name: configurations.name.clone(),
+                })
+                .collect(),
+        )
+    }
+}
+
+/// Plain data, referring to no input.
+impl Inputs for ColmenaConfigurations {
+    fn inputs(&self, _: &mut BTreeSet<String>) {}
+}
+
+impl Inherits for ColmenaConfigurations {
+    fn inherits(&self, _: &mut Inherited) {}
+}
+
+impl FormatInto<Nix> for ColmenaConfigurations {
+    fn format_into(self, tokens: &mut Tokens<Nix>) {
+        tokens.append("{");
+        tokens.indent();
+        for colmena_configuration in self.0 {
+            quote_in!(*tokens => $(attr_name(&colmena_configuration.name)) = $colmena_configuration;);
+            tokens.push();
+        }
+        tokens.unindent();
+        tokens.append("}");
+    }
+}
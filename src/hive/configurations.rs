@@ -1,8 +1,52 @@
+use crate::hive::attrs::attr_name;
+use crate::hive::flake::Inputs;
+use crate::hive::inherit::{Inherited, Inherits};
 use crate::hive::*;
+use crate::nix::value::atom;
+use crate::nix::NixValue;
 use genco::prelude::*;
+use genco::tokens::from_fn;
+use std::collections::BTreeSet;
+use std::fmt;
+
+/// Error returned when a fleet can not be built.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The bee of a host does not validate, see `Bee::validate`.
+    Bee(String, bee::Error),
+    /// The hosts are not named uniquely.
+    Naming(host_naming::Error),
+    /// Overrides for a host the fleet does not have.
+    UnknownHost(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Bee(host, error) => write!(f, "host `{}`: {}", host, error),
+            Error::Naming(error) => write!(f, "{}", error),
+            Error::UnknownHost(host) => write!(f, "the fleet has no host `{}`", host),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<host_naming::Error> for Error {
+    fn from(error: host_naming::Error) -> Self {
+        Error::Naming(error)
+    }
+}
 
 pub enum ConfigurationType {
     Import(Import),
+    /// A module written out in the `imports` list.
+    Inline(Inline),
+    /// Hand-written imports kept when the file is generated again.
+    Region(Region),
+    /// Any other expression, such as a path `./extra.nix` read back from a
+    /// filled region.
+    Value(NixValue),
     Dummy,
 }
 
@@ -12,6 +56,58 @@ impl From<Import> for ConfigurationType {
     }
 }
 
+impl From<Inline> for ConfigurationType {
+    fn from(inline: Inline) -> Self {
+        Self::Inline(inline)
+    }
+}
+
+impl From<NixValue> for ConfigurationType {
+    fn from(value: NixValue) -> Self {
+        Self::Value(value)
+    }
+}
+
+impl From<Region> for ConfigurationType {
+    fn from(region: Region) -> Self {
+        Self::Region(region)
+    }
+}
+
+impl Inputs for ConfigurationType {
+    fn inputs(&self, names: &mut BTreeSet<String>) {
+        match self {
+            ConfigurationType::Import(import) => import.inputs(names),
+            ConfigurationType::Inline(inline) => inline.inputs(names),
+            ConfigurationType::Value(value) => value.inputs(names),
+            ConfigurationType::Region(_) | ConfigurationType::Dummy => (),
+        }
+    }
+}
+
+impl Inherits for ConfigurationType {
+    fn inherits(&self, inherited: &mut Inherited) {
+        match self {
+            ConfigurationType::Import(import) => import.inherits(inherited),
+            ConfigurationType::Inline(inline) => inline.inherits(inherited),
+            ConfigurationType::Value(value) => value.inherits(inherited),
+            ConfigurationType::Region(_) | ConfigurationType::Dummy => (),
+        }
+    }
+}
+
+impl FormatInto<Nix> for ConfigurationType {
+    fn format_into(self, tokens: &mut Tokens<Nix>) {
+        match self {
+            ConfigurationType::Import(import) => tokens.append(import),
+            ConfigurationType::Inline(inline) => tokens.append(inline),
+            ConfigurationType::Region(region) => tokens.append(region),
+            ConfigurationType::Value(value) => atom(value, tokens),
+            ConfigurationType::Dummy => (),
+        }
+    }
+}
+
 pub struct Configurations {
     pub configurations: Vec<ConfigurationType>,
     /// Name do **not** included in quote.
@@ -62,31 +158,26 @@ impl Configurations {
             configurations.push(ConfigurationType::from(import))
         }
         Self {
-            configurations: configurations,
+            configurations,
             name: String::from(name),
         }
     }
 
     pub fn new_nixos_configurations(name: &str) -> Self {
-        Self {
-            configurations: vec![
-                Import::bee(name).into(),
-                Import::cell_disko_configurations(name).into(),
-                Import::cell_hardware_profiles(name).into(),
-                Import::cell_home_configurations(name).into(),
-                Import::cell_nixos_modules(name).into(),
-                Import::cell_nixos_profiles(name).into(),
-                Import::disko_module().into(),
-            ],
-            name: String::from(name),
-        }
+        Self::builder(name).bee(Import::bee(name)).build()
+    }
+
+    /// Start a NixOS host importing every standard block, see
+    /// `ConfigurationsBuilder`.
+    pub fn builder(name: &str) -> ConfigurationsBuilder {
+        ConfigurationsBuilder::nixos(name)
     }
 
     /// ```
     /// use genco::prelude::*;
     /// use honey::hive::*;
     ///
-    /// let configurations = Configurations::new_nixos_configurations1("machine1", Some("home-23-05"), "nixos-23-05", "x86_64-linux");
+    /// let configurations = Configurations::new_nixos_configurations1("machine1", Some("home-23-05"), "nixos-23-05", "x86_64-linux")?;
     ///
     /// let toks = quote!($configurations);
     ///
@@ -125,26 +216,355 @@ impl Configurations {
     ///     ],
     ///     toks.to_file_vec()?
     /// );
-    /// # Ok::<_, genco::fmt::Error>(())
+    /// # Ok::<_, Box<dyn std::error::Error>>(())
     /// ```
     pub fn new_nixos_configurations1(
         name: &str,
         home_manager: Option<&str>,
         nixpkgs: &str,
         system: &str,
-    ) -> Self {
-        let mut imports = Vec::new();
-        imports.push(Import::bee1(name, home_manager, nixpkgs, system));
-        imports.push(Import::cell_disko_configurations(name));
-        imports.push(Import::cell_hardware_profiles(name));
-        if let Some(_) = home_manager {
-            imports.push(Import::cell_home_configurations(name));
+    ) -> Result<Self, bee::Error> {
+        Ok(Self::builder(name)
+            .bee(Import::bee1(name, home_manager, nixpkgs, system)?)
+            .home(home_manager.is_some())
+            .build())
+    }
+
+    /// Darwin counterpart of `new_nixos_configurations1`, without disko and
+    /// hardware profiles.
+    ///
+    /// ```
+    /// use genco::prelude::*;
+    /// use honey::hive::*;
+    ///
+    /// let configurations = Configurations::new_darwin_configurations1("macbook1", "darwin", Some("home-23-05"), "nixpkgs-darwin", "aarch64-darwin")?;
+    ///
+    /// let toks = quote!($configurations);
+    ///
+    /// assert_eq!(
+    ///     vec![
+    ///         "{",
+    ///         "    cell,",
+    ///         "    inputs,",
+    ///         "    ...",
+    ///         "}:",
+    ///         "",
+    ///         "let",
+    ///         "    inherit (inputs) darwin;",
+    ///         "    inherit (inputs) home-23-05;",
+    ///         "    inherit (inputs) nixpkgs-darwin;",
+    ///         "    bee-macbook1 = {",
+    ///         "        bee = {",
+    ///         "            darwin = darwin;",
+    ///         "            home = home-23-05;",
+    ///         "            pkgs = nixpkgs-darwin.legacyPackages;",
+    ///         "            system = \"aarch64-darwin\";",
+    ///         "        };",
+    ///         "    };",
+    ///         "in",
+    ///         "",
+    ///         "{",
+    ///         "    imports = [",
+    ///         "        bee-macbook1",
+    ///         "        cell.darwinModules.macbook1",
+    ///         "        cell.darwinProfiles.macbook1",
+    ///         "        cell.homeConfigurations.macbook1",
+    ///         "    ];",
+    ///         "}",
+    ///     ],
+    ///     toks.to_file_vec()?
+    /// );
+    /// # Ok::<_, Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn new_darwin_configurations1(
+        name: &str,
+        darwin: &str,
+        home_manager: Option<&str>,
+        nixpkgs: &str,
+        system: &str,
+    ) -> Result<Self, bee::Error> {
+        Ok(ConfigurationsBuilder::darwin(name)
+            .bee(Import::bee3(name, darwin, home_manager, nixpkgs, system)?)
+            .home(home_manager.is_some())
+            .build())
+    }
+
+    /// Read a single configuration block, such as one written for
+    /// `Configurations`, back into the model.
+    ///
+    /// ```
+    /// use genco::prelude::*;
+    /// use honey::hive::*;
+    ///
+    /// let input = r#"
+    /// { cell, inputs, ... }:
+    /// let
+    ///   inherit (inputs) disko;
+    /// in
+    /// {
+    ///   /* Hand-edited. */
+    ///   imports = [
+    ///     cell.diskoConfigurations.my-disko-configurations
+    ///     disko.nixosModules.disko
+    ///   ];
+    /// }
+    /// "#;
+    ///
+    /// let configurations = Configurations::parse("dummy", input)?;
+    ///
+    /// let toks = quote!($configurations);
+    ///
+    /// assert_eq!(
+    ///     vec![
+    ///         "{",
+    ///         "    cell,",
+    ///         "    inputs,",
+    ///         "    ...",
+    ///         "}:",
+    ///         "",
+    ///         "let",
+    ///         "    inherit (inputs) disko;",
+    ///         "in",
+    ///         "",
+    ///         "{",
+    ///         "    imports = [",
+    ///         "        cell.diskoConfigurations.my-disko-configurations",
+    ///         "        disko.nixosModules.disko",
+    ///         "    ];",
+    ///         "}"
+    ///     ],
+    ///     toks.to_file_vec()?
+    /// );
+    /// # Ok::<_, Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn parse(name: &str, input: &str) -> Result<Self, parser::Error> {
+        parser::configurations(name, input)
+    }
+}
+
+/// The imports a `ConfigurationsBuilder` can emit, in their default order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Block {
+    Bee,
+    /// `cell.diskoConfigurations.<name>`
+    Disko,
+    /// `cell.hardwareProfiles.<name>`
+    Hardware,
+    /// `cell.homeConfigurations.<name>`
+    Home,
+    /// `cell.nixosModules.<name>` or `cell.darwinModules.<name>`
+    Modules,
+    /// `cell.nixosProfiles.<name>` or `cell.darwinProfiles.<name>`
+    Profiles,
+    /// `disko.nixosModules.disko`, emitted together with `Disko`.
+    DiskoModule,
+    /// Imports and inline modules added with `ConfigurationsBuilder::import`
+    /// and `ConfigurationsBuilder::inline`.
+    Extra,
+}
+
+const BLOCKS: [Block; 8] = [
+    Block::Bee,
+    Block::Disko,
+    Block::Hardware,
+    Block::Home,
+    Block::Modules,
+    Block::Profiles,
+    Block::DiskoModule,
+    Block::Extra,
+];
+
+/// Builds the imports of one host, one standard block at a time.
+///
+/// ```
+/// use genco::prelude::*;
+/// use honey::hive::*;
+///
+/// let configurations = Configurations::builder("vm1")
+///     .bee(Import::bee1("vm1", None, "nixos-23-05", "x86_64-linux")?)
+///     .disko(false)
+///     .home(false)
+///     .import(Import::new("inputs", "nixos-hardware", "nixosModules.common-gpu-intel"))
+///     .order([Block::Bee, Block::Extra])
+///     .build();
+///
+/// let toks = quote!($configurations);
+///
+/// assert_eq!(
+///     vec![
+///         "{",
+///         "    cell,",
+///         "    inputs,",
+///         "    ...",
+///         "}:",
+///         "",
+///         "let",
+///         "    inherit (inputs) nixos-23-05;",
+///         "    inherit (inputs) nixos-hardware;",
+///         "    bee-vm1 = {",
+///         "        bee = {",
+///         "            pkgs = nixos-23-05.legacyPackages;",
+///         "            system = \"x86_64-linux\";",
+///         "        };",
+///         "    };",
+///         "in",
+///         "",
+///         "{",
+///         "    imports = [",
+///         "        bee-vm1",
+///         "        nixos-hardware.nixosModules.common-gpu-intel",
+///         "        cell.hardwareProfiles.vm1",
+///         "        cell.nixosModules.vm1",
+///         "        cell.nixosProfiles.vm1",
+///         "    ];",
+///         "}",
+///     ],
+///     toks.to_file_vec()?
+/// );
+/// # Ok::<_, Box<dyn std::error::Error>>(())
+/// ```
+pub struct ConfigurationsBuilder {
+    bee: Option<Import>,
+    darwin: bool,
+    disabled: Vec<Block>,
+    extra: Vec<ConfigurationType>,
+    name: String,
+    order: Vec<Block>,
+}
+
+impl ConfigurationsBuilder {
+    /// Every standard block of a NixOS host enabled.
+    pub fn nixos(name: &str) -> Self {
+        Self {
+            bee: None,
+            darwin: false,
+            disabled: Vec::new(),
+            extra: Vec::new(),
+            name: String::from(name),
+            order: BLOCKS.to_vec(),
         }
-        imports.push(Import::cell_nixos_modules(name));
-        imports.push(Import::cell_nixos_profiles(name));
-        imports.push(Import::disko_module());
+    }
+
+    /// A nix-darwin host: darwin modules and profiles, no disko or hardware
+    /// profiles. Imports are sorted as for NixOS hosts, so `darwinModules`
+    /// and `darwinProfiles` come before `homeConfigurations`.
+    pub fn darwin(name: &str) -> Self {
         Self {
-            configurations: imports.into_iter().map(|import| import.into()).collect(),
+            darwin: true,
+            ..Self::nixos(name)
+        }
+        .disko(false)
+        .hardware(false)
+        .order([Block::Bee, Block::Modules, Block::Profiles])
+    }
+
+    pub fn bee(mut self, bee: Import) -> Self {
+        self.bee = Some(bee);
+        self
+    }
+
+    fn toggle(mut self, block: Block, enabled: bool) -> Self {
+        self.disabled.retain(|disabled| *disabled != block);
+        if !enabled {
+            self.disabled.push(block);
+        }
+        self
+    }
+
+    /// `cell.diskoConfigurations.<name>` and `disko.nixosModules.disko`.
+    pub fn disko(self, enabled: bool) -> Self {
+        self.toggle(Block::Disko, enabled)
+            .toggle(Block::DiskoModule, enabled)
+    }
+
+    pub fn hardware(self, enabled: bool) -> Self {
+        self.toggle(Block::Hardware, enabled)
+    }
+
+    pub fn home(self, enabled: bool) -> Self {
+        self.toggle(Block::Home, enabled)
+    }
+
+    pub fn modules(self, enabled: bool) -> Self {
+        self.toggle(Block::Modules, enabled)
+    }
+
+    pub fn profiles(self, enabled: bool) -> Self {
+        self.toggle(Block::Profiles, enabled)
+    }
+
+    /// Add an extra import, emitted at `Block::Extra`. An import the host
+    /// already has is left out.
+    pub fn import(mut self, import: Import) -> Self {
+        self.extra.push(import.into());
+        self
+    }
+
+    /// Add an inline module, emitted at `Block::Extra`.
+    pub fn inline(mut self, inline: Inline) -> Self {
+        self.extra.push(inline.into());
+        self
+    }
+
+    /// Add a region for hand-written imports, emitted at `Block::Extra`.
+    pub fn region(mut self, name: &str) -> Self {
+        self.extra.push(Region::new(name).into());
+        self
+    }
+
+    /// Emit `blocks` first, in the given order; the others follow in their
+    /// default order.
+    pub fn order<T>(mut self, blocks: T) -> Self
+    where
+        T: IntoIterator<Item = Block>,
+    {
+        let mut order: Vec<Block> = Vec::new();
+        for block in blocks.into_iter().chain(BLOCKS) {
+            if !order.contains(&block) {
+                order.push(block);
+            }
+        }
+        self.order = order;
+        self
+    }
+
+    pub fn build(self) -> Configurations {
+        let name = self.name.as_str();
+        let mut bee = self.bee;
+        let mut extra = Some(self.extra);
+        let mut configurations: Vec<ConfigurationType> = Vec::new();
+        for block in self.order {
+            if self.disabled.contains(&block) {
+                continue;
+            }
+            let import = match block {
+                Block::Bee => bee.take(),
+                Block::Disko => Some(Import::cell_disko_configurations(name)),
+                Block::Hardware => Some(Import::cell_hardware_profiles(name)),
+                Block::Home => Some(Import::cell_home_configurations(name)),
+                Block::Modules if self.darwin => Some(Import::cell_darwin_modules(name)),
+                Block::Modules => Some(Import::cell_nixos_modules(name)),
+                Block::Profiles if self.darwin => Some(Import::cell_darwin_profiles(name)),
+                Block::Profiles => Some(Import::cell_nixos_profiles(name)),
+                Block::DiskoModule => Some(Import::disko_module()),
+                Block::Extra => {
+                    configurations.extend(extra.take().into_iter().flatten());
+                    None
+                }
+            };
+            configurations.extend(import.map(ConfigurationType::from));
+        }
+        let mut imports: Vec<NixValue> = Vec::new();
+        configurations.retain(|configuration| match configuration {
+            ConfigurationType::Import(import) if imports.contains(&import.name) => false,
+            ConfigurationType::Import(import) => {
+                imports.push(import.name.clone());
+                true
+            }
+            _ => true,
+        });
+        Configurations {
+            configurations,
             name: String::from(name),
         }
     }
@@ -171,6 +591,22 @@ impl From<Configurations> for Imports {
     }
 }
 
+impl Inputs for Configurations {
+    fn inputs(&self, names: &mut BTreeSet<String>) {
+        for configuration in &self.configurations {
+            configuration.inputs(names);
+        }
+    }
+}
+
+impl Inherits for Configurations {
+    fn inherits(&self, inherited: &mut Inherited) {
+        for configuration in &self.configurations {
+            configuration.inherits(inherited);
+        }
+    }
+}
+
 impl FormatInto<Nix> for Configurations {
     /// ```
     /// use genco::prelude::*;
@@ -210,18 +646,313 @@ impl FormatInto<Nix> for Configurations {
     /// # Ok::<_, genco::fmt::Error>(())
     /// ```
     fn format_into(self, tokens: &mut Tokens<Nix>) {
-        let imports = Imports::from(self);
-        quote_in! { *tokens =>
+        let inherited = Inherited::of(&self);
+        let imports = from_fn(move |tokens| {
+            tokens.append("[");
+            tokens.indent();
+            for configuration in self.configurations {
+                if !matches!(configuration, ConfigurationType::Dummy) {
+                    tokens.append(configuration);
+                    tokens.push();
+                }
+            }
+            tokens.unindent();
+            tokens.append("]");
+        });
+        let configurations = quote! {
             {
                 imports = $(imports);
             }
+        };
+        tokens.append(inherit::unshadow(&configurations, &inherited));
+    }
+}
+
+/// Settings of one host that differ from the rest of its fleet, see
+/// `NixosConfigurationsBuilder`.
+#[derive(Clone, Default)]
+pub struct Overrides {
+    pub system: Option<String>,
+    pub nixpkgs: Option<String>,
+    /// `Some(None)` leaves out home manager.
+    pub home_manager: Option<Option<String>>,
+    /// Imported after the imports of the fleet.
+    pub imports: Vec<Import>,
+    /// Blocks turned on or off, in order, after the ones of the fleet.
+    pub blocks: Vec<(Block, bool)>,
+}
+
+impl Overrides {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn system(mut self, system: &str) -> Self {
+        self.system = Some(String::from(system));
+        self
+    }
+
+    pub fn nixpkgs(mut self, nixpkgs: &str) -> Self {
+        self.nixpkgs = Some(String::from(nixpkgs));
+        self
+    }
+
+    pub fn home_manager(mut self, home_manager: Option<&str>) -> Self {
+        self.home_manager = Some(home_manager.map(String::from));
+        self
+    }
+
+    pub fn import(mut self, import: Import) -> Self {
+        self.imports.push(import);
+        self
+    }
+
+    /// Import `block`, even if the fleet leaves it out.
+    pub fn enable(mut self, block: Block) -> Self {
+        self.blocks.push((block, true));
+        self
+    }
+
+    /// Leave out `block`.
+    pub fn disable(mut self, block: Block) -> Self {
+        self.blocks.push((block, false));
+        self
+    }
+}
+
+/// Builds a fleet sharing `nixpkgs`, `system` and home manager, with
+/// `Overrides` for single hosts.
+///
+/// ```
+/// use genco::prelude::*;
+/// use honey::hive::*;
+///
+/// let nixos_configurations = NixosConfigurations::builder(HostPattern::new("machine", 3), "nixos-23-05", "x86_64-linux")
+///     .home_manager(Some("home-23-05"))
+///     .disable(Block::Disko)
+///     .host("machine00", Overrides::new().enable(Block::Disko))
+///     .host("machine01", Overrides::new().system("aarch64-linux").disable(Block::Home))
+///     .host(
+///         "machine02",
+///         Overrides::new()
+///             .nixpkgs("nixos-unstable")
+///             .home_manager(None)
+///             .import(Import::new("inputs", "nixos-hardware", "nixosModules.common-cpu-amd")),
+///     )
+///     .build()?;
+///
+/// let toks = quote!($nixos_configurations);
+///
+/// assert_eq!(
+///     vec![
+///         "{",
+///         "    cell,",
+///         "    inputs,",
+///         "    ...",
+///         "}:",
+///         "",
+///         "let",
+///         "    inherit (inputs) disko;",
+///         "    inherit (inputs) home-23-05;",
+///         "    inherit (inputs) nixos-23-05;",
+///         "    inherit (inputs) nixos-hardware;",
+///         "    inherit (inputs) nixos-unstable;",
+///         "    bee-machine00 = {",
+///         "        bee = {",
+///         "            home = home-23-05;",
+///         "            pkgs = nixos-23-05.legacyPackages;",
+///         "            system = \"x86_64-linux\";",
+///         "        };",
+///         "    };",
+///         "    bee-machine01 = {",
+///         "        bee = {",
+///         "            home = home-23-05;",
+///         "            pkgs = nixos-23-05.legacyPackages;",
+///         "            system = \"aarch64-linux\";",
+///         "        };",
+///         "    };",
+///         "    bee-machine02 = {",
+///         "        bee = {",
+///         "            pkgs = nixos-unstable.legacyPackages;",
+///         "            system = \"x86_64-linux\";",
+///         "        };",
+///         "    };",
+///         "in",
+///         "",
+///         "{",
+///         "    machine00 = {",
+///         "        imports = [",
+///         "            bee-machine00",
+///         "            cell.diskoConfigurations.machine00",
+///         "            cell.hardwareProfiles.machine00",
+///         "            cell.homeConfigurations.machine00",
+///         "            cell.nixosModules.machine00",
+///         "            cell.nixosProfiles.machine00",
+///         "            disko.nixosModules.disko",
+///         "        ];",
+///         "    };",
+///         "    machine01 = {",
+///         "        imports = [",
+///         "            bee-machine01",
+///         "            cell.hardwareProfiles.machine01",
+///         "            cell.nixosModules.machine01",
+///         "            cell.nixosProfiles.machine01",
+///         "        ];",
+///         "    };",
+///         "    machine02 = {",
+///         "        imports = [",
+///         "            bee-machine02",
+///         "            cell.hardwareProfiles.machine02",
+///         "            cell.nixosModules.machine02",
+///         "            cell.nixosProfiles.machine02",
+///         "            nixos-hardware.nixosModules.common-cpu-amd",
+///         "        ];",
+///         "    };",
+///         "}",
+///     ],
+///     toks.to_file_vec()?
+/// );
+/// # Ok::<_, Box<dyn std::error::Error>>(())
+/// ```
+pub struct NixosConfigurationsBuilder {
+    hosts: Vec<String>,
+    defaults: Overrides,
+    overrides: Vec<(String, Overrides)>,
+    /// The first error, returned by `build`.
+    error: Option<Error>,
+}
+
+impl NixosConfigurationsBuilder {
+    pub fn new<T>(naming: T, nixpkgs: &str, system: &str) -> Self
+    where
+        T: Into<HostNaming>,
+    {
+        let (hosts, error) = match naming.into().names() {
+            Ok(hosts) => (hosts, None),
+            Err(error) => (Vec::new(), Some(Error::from(error))),
+        };
+        Self {
+            hosts,
+            defaults: Overrides::new().nixpkgs(nixpkgs).system(system),
+            overrides: Vec::new(),
+            error,
+        }
+    }
+
+    pub fn home_manager(mut self, home_manager: Option<&str>) -> Self {
+        self.defaults = self.defaults.home_manager(home_manager);
+        self
+    }
+
+    /// Add an import to every host.
+    pub fn import(mut self, import: Import) -> Self {
+        self.defaults = self.defaults.import(import);
+        self
+    }
+
+    /// Leave out `block` on every host.
+    pub fn disable(mut self, block: Block) -> Self {
+        self.defaults = self.defaults.disable(block);
+        self
+    }
+
+    /// Override the settings of the host `name`. Overrides of the same host
+    /// add up. `build` fails if the fleet has no host `name`:
+    ///
+    /// ```
+    /// use honey::hive::*;
+    /// use honey::hive::configurations::Error;
+    ///
+    /// let result = NixosConfigurations::builder(HostPattern::new("machine", 2), "nixos-23-05", "x86_64-linux")
+    ///     .host("machine02", Overrides::new().system("aarch64-linux"))
+    ///     .build();
+    ///
+    /// assert_eq!(Some(Error::UnknownHost(String::from("machine02"))), result.err());
+    /// ```
+    pub fn host(mut self, name: &str, overrides: Overrides) -> Self {
+        if !self.hosts.iter().any(|host| host == name) {
+            self.error
+                .get_or_insert(Error::UnknownHost(String::from(name)));
+        }
+        self.overrides.push((String::from(name), overrides));
+        self
+    }
+
+    /// Fails when the hosts are not named uniquely, on overrides for an
+    /// unknown host, or on the first host whose bee does not validate.
+    ///
+    /// ```
+    /// use honey::hive::*;
+    /// use honey::hive::bee;
+    /// use honey::hive::configurations::Error;
+    ///
+    /// let result = NixosConfigurations::builder(HostPattern::new("machine", 2), "nixos-23-05", "x86_64-linux")
+    ///     .host("machine01", Overrides::new().system("foo"))
+    ///     .build();
+    ///
+    /// assert_eq!(
+    ///     Some(Error::Bee(String::from("machine01"), bee::Error::InvalidSystem(String::from("foo")))),
+    ///     result.err()
+    /// );
+    ///
+    /// let result = NixosConfigurations::builder(HostNaming::list(["web", "web"]), "nixos-23-05", "x86_64-linux")
+    ///     .build();
+    ///
+    /// assert_eq!("duplicate host `web`", result.err().unwrap().to_string());
+    /// ```
+    pub fn build(self) -> Result<NixosConfigurations, Error> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        let mut configurations = Vec::new();
+        for name in &self.hosts {
+            let mut host = self.defaults.clone();
+            for (_, overrides) in self.overrides.iter().filter(|(other, _)| other == name) {
+                let overrides = overrides.clone();
+                host.system = overrides.system.or(host.system);
+                host.nixpkgs = overrides.nixpkgs.or(host.nixpkgs);
+                host.home_manager = overrides.home_manager.or(host.home_manager);
+                host.imports.extend(overrides.imports);
+                host.blocks.extend(overrides.blocks);
+            }
+            let home_manager = host.home_manager.flatten();
+            let bee = Import::bee1(
+                name,
+                home_manager.as_deref(),
+                host.nixpkgs.as_deref().unwrap_or_default(),
+                host.system.as_deref().unwrap_or_default(),
+            )
+            .map_err(|error| Error::Bee(name.clone(), error))?;
+            let mut builder = Configurations::builder(name)
+                .bee(bee)
+                .home(home_manager.is_some());
+            for (block, enabled) in host.blocks {
+                builder = match block {
+                    Block::Disko => builder.disko(enabled),
+                    block => builder.toggle(block, enabled),
+                };
+            }
+            for import in host.imports {
+                builder = builder.import(import);
+            }
+            configurations.push(builder.build());
         }
+        Ok(NixosConfigurations(configurations))
     }
 }
 
 pub struct NixosConfigurations(pub Vec<Configurations>);
 
 impl NixosConfigurations {
+    /// Start a fleet of hosts named by `naming`, see
+    /// `NixosConfigurationsBuilder`.
+    pub fn builder<T>(naming: T, nixpkgs: &str, system: &str) -> NixosConfigurationsBuilder
+    where
+        T: Into<HostNaming>,
+    {
+        NixosConfigurationsBuilder::new(naming, nixpkgs, system)
+    }
+
     /// ```
     /// use genco::prelude::*;
     /// use honey::hive::*;
@@ -276,7 +1007,7 @@ impl NixosConfigurations {
     /// use genco::prelude::*;
     /// use honey::hive::*;
     ///
-    /// let nixos_configurations = NixosConfigurations::new1("machine", 2, Some("home-23-05"), "nixos-23-05", "x86_64-linux");
+    /// let nixos_configurations = NixosConfigurations::new1("machine", 2, Some("home-23-05"), "nixos-23-05", "x86_64-linux")?;
     ///
     /// let toks = quote!($nixos_configurations);
     ///
@@ -335,7 +1066,7 @@ impl NixosConfigurations {
     ///     ],
     ///     toks.to_file_vec()?
     /// );
-    /// # Ok::<_, genco::fmt::Error>(())
+    /// # Ok::<_, Box<dyn std::error::Error>>(())
     /// ```
     pub fn new1(
         prefix: &str,
@@ -343,18 +1074,157 @@ impl NixosConfigurations {
         home_manager: Option<&str>,
         nixpkgs: &str,
         system: &str,
-    ) -> Self {
-        let mut configurations = Vec::new();
-        for i in 0..number {
-            let name = &format!("{}{:02}", prefix, i);
-            configurations.push(Configurations::new_nixos_configurations1(
-                name,
-                home_manager,
-                nixpkgs,
-                system,
-            ))
+    ) -> Result<Self, Error> {
+        Self::new2(
+            HostPattern::new(prefix, number),
+            home_manager,
+            nixpkgs,
+            system,
+        )
+    }
+
+    /// Like `new1`, for hosts named by `naming`.
+    pub fn new2<T>(
+        naming: T,
+        home_manager: Option<&str>,
+        nixpkgs: &str,
+        system: &str,
+    ) -> Result<Self, Error>
+    where
+        T: Into<HostNaming>,
+    {
+        Self::builder(naming, nixpkgs, system)
+            .home_manager(home_manager)
+            .build()
+    }
+
+    /// An entry per host with only a region for its imports, for blocks such
+    /// as `nixosModules` that are filled in by hand.
+    ///
+    /// ```
+    /// use genco::prelude::*;
+    /// use honey::hive::*;
+    ///
+    /// let stubs = NixosConfigurations::stubs(&[String::from("machine00")]);
+    ///
+    /// let toks = quote!($stubs);
+    ///
+    /// assert_eq!(
+    ///     vec![
+    ///         "{",
+    ///         "    machine00 = {",
+    ///         "        imports = [",
+    ///         "            # honey:begin machine00",
+    ///         "            # honey:end",
+    ///         "        ];",
+    ///         "    };",
+    ///         "}",
+    ///     ],
+    ///     toks.to_vec()?
+    /// );
+    /// # Ok::<_, genco::fmt::Error>(())
+    /// ```
+    pub fn stubs(hosts: &[String]) -> Self {
+        Self(
+            hosts
+                .iter()
+                .map(|host| Configurations {
+                    configurations: vec![Region::new(host).into()],
+                    name: host.clone(),
+                })
+                .collect(),
+        )
+    }
+
+    /// Read a `nixosConfigurations` block back into the model, so that files
+    /// written by hand or by an earlier run can be changed from code.
+    ///
+    /// ```
+    /// use genco::prelude::*;
+    /// use honey::hive::*;
+    ///
+    /// let nixos_configurations = NixosConfigurations::new1("machine", 2, Some("home-23-05"), "nixos-23-05", "x86_64-linux")?;
+    ///
+    /// let input = quote!($nixos_configurations).to_file_string()?;
+    ///
+    /// let parsed = NixosConfigurations::parse(&input)?;
+    ///
+    /// assert_eq!(input, quote!($parsed).to_file_string()?);
+    ///
+    /// let error = NixosConfigurations::parse("{ machine1 = { imports = [ foo.bar ]; }; }").err();
+    ///
+    /// assert_eq!(
+    ///     Some(String::from("host `machine1`: unknown identifier `foo` in `foo.bar`")),
+    ///     error.map(|error| error.to_string())
+    /// );
+    ///
+    /// // A region filled by hand, with any expression and `let` binding.
+    /// let input = r#"
+    /// { cell, inputs, ... }:
+    /// let
+    ///   inherit (inputs) nixpkgs;
+    ///   overlay = import ./overlay.nix;
+    /// in
+    /// {
+    ///   machine00 = {
+    ///     imports = [
+    ///       cell.nixosModules.machine00
+    ///       ## honey:begin machine00
+    ///       ./extra.nix
+    ///       (overlay nixpkgs)
+    ///       ## honey:end
+    ///     ];
+    ///   };
+    /// }
+    /// "#;
+    ///
+    /// let parsed = NixosConfigurations::parse(input)?;
+    ///
+    /// assert_eq!(
+    ///     vec![
+    ///         "{",
+    ///         "    cell,",
+    ///         "    inputs,",
+    ///         "    ...",
+    ///         "}:",
+    ///         "",
+    ///         "let",
+    ///         "    inherit (inputs) nixpkgs;",
+    ///         "    overlay = import ./overlay.nix;",
+    ///         "in",
+    ///         "",
+    ///         "{",
+    ///         "    machine00 = {",
+    ///         "        imports = [",
+    ///         "            cell.nixosModules.machine00",
+    ///         "            ./extra.nix",
+    ///         "            (overlay nixpkgs)",
+    ///         "        ];",
+    ///         "    };",
+    ///         "}",
+    ///     ],
+    ///     quote!($parsed).to_file_vec()?
+    /// );
+    /// # Ok::<_, Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn parse(input: &str) -> Result<Self, parser::Error> {
+        parser::nixos_configurations(input)
+    }
+}
+
+impl Inputs for NixosConfigurations {
+    fn inputs(&self, names: &mut BTreeSet<String>) {
+        for configurations in &self.0 {
+            configurations.inputs(names);
+        }
+    }
+}
+
+impl Inherits for NixosConfigurations {
+    fn inherits(&self, inherited: &mut Inherited) {
+        for configurations in &self.0 {
+            configurations.inherits(inherited);
         }
-        Self(configurations)
     }
 }
 
@@ -427,13 +1297,16 @@ impl FormatInto<Nix> for NixosConfigurations {
     /// # Ok::<_, genco::fmt::Error>(())
     /// ```
     fn format_into(self, tokens: &mut Tokens<Nix>) {
-        tokens.append("{");
-        tokens.indent();
+        let inherited = Inherited::of(&self);
+        let mut hosts = Tokens::new();
+        hosts.append("{");
+        hosts.indent();
         for configurations in self.0 {
-            quote_in!(*tokens => $(configurations.name.clone()) = $configurations;);
-            tokens.push();
+            quote_in!(hosts => $(attr_name(&configurations.name)) = $configurations;);
+            hosts.push();
         }
-        tokens.unindent();
-        tokens.append("}");
+        hosts.unindent();
+        hosts.append("}");
+        tokens.append(inherit::unshadow(&hosts, &inherited));
     }
 }
@@ -0,0 +1,154 @@
+use crate::hive::configurations::Error;
+use crate::hive::flake::Inputs;
+use crate::hive::inherit::{Inherited, Inherits};
+use crate::hive::*;
+use genco::prelude::*;
+use std::collections::BTreeSet;
+
+pub struct DarwinConfigurations(pub Vec<Configurations>);
+
+impl DarwinConfigurations {
+    /// ```
+    /// use genco::prelude::*;
+    /// use honey::hive::*;
+    ///
+    /// let darwin_configurations = DarwinConfigurations::new1("macbook", 2, "darwin", None, "nixpkgs-darwin", "aarch64-darwin")?;
+    ///
+    /// let toks = quote!($darwin_configurations);
+    ///
+    /// assert_eq!(
+    ///     vec![
+    ///         "{",
+    ///         "    cell,",
+    ///         "    inputs,",
+    ///         "    ...",
+    ///         "}:",
+    ///         "",
+    ///         "let",
+    ///         "    inherit (inputs) darwin;",
+    ///         "    inherit (inputs) nixpkgs-darwin;",
+    ///         "    bee-macbook00 = {",
+    ///         "        bee = {",
+    ///         "            darwin = darwin;",
+    ///         "            pkgs = nixpkgs-darwin.legacyPackages;",
+    ///         "            system = \"aarch64-darwin\";",
+    ///         "        };",
+    ///         "    };",
+    ///         "    bee-macbook01 = {",
+    ///         "        bee = {",
+    ///         "            darwin = darwin;",
+    ///         "            pkgs = nixpkgs-darwin.legacyPackages;",
+    ///         "            system = \"aarch64-darwin\";",
+    ///         "        };",
+    ///         "    };",
+    ///         "in",
+    ///         "",
+    ///         "{",
+    ///         "    macbook00 = {",
+    ///         "        imports = [",
+    ///         "            bee-macbook00",
+    ///         "            cell.darwinModules.macbook00",
+    ///         "            cell.darwinProfiles.macbook00",
+    ///         "        ];",
+    ///         "    };",
+    ///         "    macbook01 = {",
+    ///         "        imports = [",
+    ///         "            bee-macbook01",
+    ///         "            cell.darwinModules.macbook01",
+    ///         "            cell.darwinProfiles.macbook01",
+    ///         "        ];",
+    ///         "    };",
+    ///         "}",
+    ///     ],
+    ///     toks.to_file_vec()?
+    /// );
+    /// # Ok::<_, Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn new1(
+        prefix: &str,
+        number: u32,
+        darwin: &str,
+        home_manager: Option<&str>,
+        nixpkgs: &str,
+        system: &str,
+    ) -> Result<Self, Error> {
+        Self::new2(
+            HostPattern::new(prefix, number),
+            darwin,
+            home_manager,
+            nixpkgs,
+            system,
+        )
+    }
+
+    /// Like `new1`, for hosts named by `naming`.
+    pub fn new2<T>(
+        naming: T,
+        darwin: &str,
+        home_manager: Option<&str>,
+        nixpkgs: &str,
+        system: &str,
+    ) -> Result<Self, Error>
+    where
+        T: Into<HostNaming>,
+    {
+        let configurations = naming
+            .into()
+            .names()?
+            .iter()
+            .map(|name| {
+                Configurations::new_darwin_configurations1(
+                    name,
+                    darwin,
+                    home_manager,
+                    nixpkgs,
+                    system,
+                )
+                .map_err(|error| Error::Bee(name.clone(), error))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self(configurations))
+    }
+
+    /// Read a `darwinConfigurations` block back into the model.
+    ///
+    /// ```
+    /// use genco::prelude::*;
+    /// use honey::hive::*;
+    ///
+    /// let darwin_configurations = DarwinConfigurations::new1("macbook", 1, "darwin", Some("home-23-05"), "nixpkgs-darwin", "aarch64-darwin")?;
+    ///
+    /// let input = quote!($darwin_configurations).to_file_string()?;
+    ///
+    /// let parsed = DarwinConfigurations::parse(&input)?;
+    ///
+    /// assert_eq!(input, quote!($parsed).to_file_string()?);
+    /// # Ok::<_, Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn parse(input: &str) -> Result<Self, parser::Error> {
+        Ok(Self(NixosConfigurations::parse(input)?.0))
+    }
+}
+
+impl Inputs for DarwinConfigurations {
+    fn inputs(&self, names: &mut BTreeSet<String>) {
+        for configurations in &self.0 {
+            configurations.inputs(names);
+        }
+    }
+}
+
+impl Inherits for DarwinConfigurations {
+    fn inherits(&self, inherited: &mut Inherited) {
+        for configurations in &self.0 {
+            configurations.inherits(inherited);
+        }
+    }
+}
+
+impl FormatInto<Nix> for DarwinConfigurations {
+    fn format_into(self, tokens: &mut Tokens<Nix>) {
+        // Both blocks are an attribute set of configurations per host.
+        tokens.append(NixosConfigurations(self.0));
+    }
+}
@@ -0,0 +1,274 @@
+use crate::hive::flake::Inputs;
+use crate::hive::inherit::{Inherited, Inherits};
+use crate::hive::*;
+use crate::nix::NixValue;
+use genco::prelude::*;
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// An entry of the devshell menu.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Command {
+    pub name: String,
+    pub help: Option<String>,
+    pub category: Option<String>,
+    /// Shell script run by the command.
+    pub command: Option<String>,
+    /// Attribute path of a nixpkgs package providing the command, e.g.
+    /// `treefmt`.
+    pub package: Option<String>,
+}
+
+impl Command {
+    pub fn new(name: &str, help: &str) -> Self {
+        Self {
+            name: String::from(name),
+            help: Some(String::from(help)),
+            ..Default::default()
+        }
+    }
+
+    pub fn category(mut self, category: &str) -> Self {
+        self.category = Some(String::from(category));
+        self
+    }
+
+    pub fn command(mut self, command: &str) -> Self {
+        self.command = Some(String::from(command));
+        self
+    }
+
+    pub fn package(mut self, package: &str) -> Self {
+        self.package = Some(String::from(package));
+        self
+    }
+}
+
+/// `nixpkgs.<path>`, the nixpkgs std hands to cells.
+fn package(path: &str) -> NixValue {
+    NixValue::select(NixValue::inherit("inputs", "nixpkgs"), path)
+}
+
+impl From<Command> for NixValue {
+    fn from(command: Command) -> Self {
+        let mut attrs = vec![(vec![String::from("name")], command.name.into())];
+        let strings = [
+            ("help", command.help),
+            ("category", command.category),
+            ("command", command.command),
+        ];
+        for (name, value) in strings {
+            if let Some(value) = value {
+                attrs.push((vec![String::from(name)], value.into()));
+            }
+        }
+        if let Some(path) = command.package {
+            attrs.push((vec![String::from("package")], package(&path)));
+        }
+        NixValue::AttrSet(attrs)
+    }
+}
+
+/// A shell made with `std.lib.dev.mkShell`.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Devshell {
+    /// Attribute name and `name` of the shell.
+    pub name: String,
+    /// Attribute paths of nixpkgs packages, e.g. `git`.
+    #[serde(default)]
+    pub packages: Vec<String>,
+    #[serde(default)]
+    pub commands: Vec<Command>,
+    /// Environment variables by name.
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    /// Nixago configurations as attribute paths rooted in a cell argument,
+    /// e.g. `cell.configs.treefmt`.
+    #[serde(default)]
+    pub nixago: Vec<String>,
+}
+
+impl Devshell {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: String::from(name),
+            ..Default::default()
+        }
+    }
+
+    pub fn package(mut self, package: &str) -> Self {
+        self.packages.push(String::from(package));
+        self
+    }
+
+    pub fn command(mut self, command: Command) -> Self {
+        self.commands.push(command);
+        self
+    }
+
+    pub fn env(mut self, name: &str, value: &str) -> Self {
+        self.env.insert(String::from(name), String::from(value));
+        self
+    }
+
+    pub fn nixago(mut self, nixago: &str) -> Self {
+        self.nixago.push(String::from(nixago));
+        self
+    }
+}
+
+impl From<Devshell> for NixValue {
+    fn from(devshell: Devshell) -> Self {
+        let mut attrs = vec![(vec![String::from("name")], devshell.name.into())];
+        let mut list = |name: &str, values: Vec<NixValue>| {
+            if !values.is_empty() {
+                attrs.push((vec![String::from(name)], NixValue::List(values)));
+            }
+        };
+        list(
+            "packages",
+            devshell.packages.iter().map(|path| package(path)).collect(),
+        );
+        list(
+            "commands",
+            devshell.commands.into_iter().map(Into::into).collect(),
+        );
+        list(
+            "env",
+            devshell
+                .env
+                .into_iter()
+                .map(|(name, value)| {
+                    NixValue::AttrSet(vec![
+                        (vec![String::from("name")], name.into()),
+                        (vec![String::from("value")], value.into()),
+                    ])
+                })
+                .collect(),
+        );
+        list(
+            "nixago",
+            devshell
+                .nixago
+                .iter()
+                .map(|path| Import::new1(path).name)
+                .collect(),
+        );
+        let mk_shell = NixValue::select(NixValue::inherit("inputs", "std"), "lib.dev.mkShell");
+        NixValue::apply(mk_shell, NixValue::AttrSet(attrs))
+    }
+}
+
+impl Inputs for Devshell {
+    fn inputs(&self, names: &mut BTreeSet<String>) {
+        NixValue::from(self.clone()).inputs(names);
+    }
+}
+
+impl Inherits for Devshell {
+    fn inherits(&self, inherited: &mut Inherited) {
+        NixValue::from(self.clone()).inherits(inherited);
+    }
+}
+
+impl FormatInto<Nix> for Devshell {
+    fn format_into(self, tokens: &mut Tokens<Nix>) {
+        tokens.append(NixValue::from(self));
+    }
+}
+
+/// The `devshells` block.
+pub struct Devshells(pub Vec<Devshell>);
+
+impl Inputs for Devshells {
+    fn inputs(&self, names: &mut BTreeSet<String>) {
+        for devshell in &self.0 {
+            devshell.inputs(names);
+        }
+    }
+}
+
+impl Inherits for Devshells {
+    fn inherits(&self, inherited: &mut Inherited) {
+        for devshell in &self.0 {
+            devshell.inherits(inherited);
+        }
+    }
+}
+
+impl FormatInto<Nix> for Devshells {
+    /// ```
+    /// use genco::prelude::*;
+    /// use honey::hive::*;
+    /// use honey::hive::devshells::Command;
+    ///
+    /// let devshells = Devshells(vec![
+    ///     Devshell::new("default")
+    ///         .package("git")
+    ///         .command(Command::new("fmt", "Format the repository").category("dev").package("treefmt"))
+    ///         .command(Command::new("deploy", "Deploy every host").command("colmena apply"))
+    ///         .env("NIX_CONFIG", "experimental-features = nix-command flakes")
+    ///         .nixago("cell.configs.treefmt"),
+    /// ]);
+    ///
+    /// let toks = quote!($devshells);
+    ///
+    /// assert_eq!(
+    ///     vec![
+    ///         "{",
+    ///         "    cell,",
+    ///         "    inputs,",
+    ///         "    ...",
+    ///         "}:",
+    ///         "",
+    ///         "let",
+    ///         "    inherit (inputs) nixpkgs;",
+    ///         "    inherit (inputs) std;",
+    ///         "in",
+    ///         "",
+    ///         "{",
+    ///         "    default = std.lib.dev.mkShell {",
+    ///         "        name = \"default\";",
+    ///         "        packages = [",
+    ///         "            nixpkgs.git",
+    ///         "        ];",
+    ///         "        commands = [",
+    ///         "            {",
+    ///         "                name = \"fmt\";",
+    ///         "                help = \"Format the repository\";",
+    ///         "                category = \"dev\";",
+    ///         "                package = nixpkgs.treefmt;",
+    ///         "            }",
+    ///         "            {",
+    ///         "                name = \"deploy\";",
+    ///         "                help = \"Deploy every host\";",
+    ///         "                command = \"colmena apply\";",
+    ///         "            }",
+    ///         "        ];",
+    ///         "        env = [",
+    ///         "            {",
+    ///         "                name = \"NIX_CONFIG\";",
+    ///         "                value = \"experimental-features = nix-command flakes\";",
+    ///         "            }",
+    ///         "        ];",
+    ///         "        nixago = [",
+    ///         "            cell.configs.treefmt",
+    ///         "        ];",
+    ///         "    };",
+    ///         "}",
+    ///     ],
+    ///     toks.to_file_vec()?
+    /// );
+    /// # Ok::<_, genco::fmt::Error>(())
+    /// ```
+    fn format_into(self, tokens: &mut Tokens<Nix>) {
+        let attrs = self
+            .0
+            .into_iter()
+            .map(|devshell| (vec![devshell.name.clone()], devshell.into()))
+            .collect();
+        tokens.append(NixValue::AttrSet(attrs));
+    }
+}
@@ -0,0 +1,822 @@
+use crate::hive::attrs::{attr_name, attrs, strings};
+use crate::hive::flake::Inputs;
+use crate::hive::inherit::{Inherited, Inherits};
+use genco::prelude::*;
+use std::collections::BTreeSet;
+use std::fmt;
+
+/// Error returned when a disko layout can not work.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Error {
+    /// Two filesystems are mounted at the same place.
+    DuplicateMountpoint(String, String),
+    /// Two devices, partitions, volumes or datasets share a name.
+    DuplicateName(String, String),
+    /// A UEFI host has no `EF00` partition.
+    MissingEsp(String),
+    /// A GPT table is not placed directly on a disk or RAID array.
+    MisplacedGpt(String),
+    /// A physical volume, ZFS member or RAID member refers to nothing.
+    UnknownDevice(String, String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::DuplicateMountpoint(host, mountpoint) => {
+                write!(f, "host `{}`: `{}` is mounted twice", host, mountpoint)
+            }
+            Error::DuplicateName(host, name) => {
+                write!(f, "host `{}`: `{}` is defined twice", host, name)
+            }
+            Error::MissingEsp(host) => {
+                write!(f, "host `{}`: UEFI boot needs an EF00 partition", host)
+            }
+            Error::MisplacedGpt(host) => write!(
+                f,
+                "host `{}`: a GPT table must sit directly on a disk or RAID array",
+                host
+            ),
+            Error::UnknownDevice(host, name) => {
+                write!(f, "host `{}`: unknown device `{}`", host, name)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+fn options(items: Vec<(String, String)>) -> impl FormatInto<Nix> {
+    attrs(
+        items
+            .into_iter()
+            .map(|(name, value)| (name, quoted(value)))
+            .collect(),
+    )
+}
+
+/// What a disk, partition, logical volume or RAID array holds.
+pub enum Content {
+    Gpt(Vec<Partition>),
+    Filesystem {
+        format: String,
+        mountpoint: Option<String>,
+        mount_options: Vec<String>,
+    },
+    Swap {
+        random_encryption: bool,
+    },
+    /// LUKS container, opened as `/dev/mapper/<name>`.
+    Luks {
+        name: String,
+        content: Box<Content>,
+    },
+    /// LVM physical volume of the volume group `vg`.
+    LvmPv {
+        vg: String,
+    },
+    Btrfs {
+        mountpoint: Option<String>,
+        subvolumes: Vec<Subvolume>,
+    },
+    /// Member of the ZFS pool `pool`.
+    Zfs {
+        pool: String,
+    },
+    /// Member of the mdadm array `name`.
+    Mdraid {
+        name: String,
+    },
+}
+
+impl Content {
+    pub fn filesystem(format: &str, mountpoint: &str) -> Self {
+        Self::Filesystem {
+            format: String::from(format),
+            mountpoint: Some(String::from(mountpoint)),
+            mount_options: Vec::new(),
+        }
+    }
+
+    pub fn luks(name: &str, content: Content) -> Self {
+        Self::Luks {
+            name: String::from(name),
+            content: Box::new(content),
+        }
+    }
+
+    /// Every content nested in this one, this one included.
+    fn walk<'a>(&'a self, contents: &mut Vec<&'a Content>) {
+        contents.push(self);
+        match self {
+            Content::Gpt(partitions) => {
+                for partition in partitions {
+                    if let Some(content) = &partition.content {
+                        content.walk(contents);
+                    }
+                }
+            }
+            Content::Luks { content, .. } => content.walk(contents),
+            _ => {}
+        }
+    }
+}
+
+impl FormatInto<Nix> for Content {
+    /// ```
+    /// use genco::prelude::*;
+    /// use honey::hive::*;
+    ///
+    /// let content = Content::luks("crypted", Content::Btrfs {
+    ///     mountpoint: None,
+    ///     subvolumes: vec![
+    ///         Subvolume::new("/root", "/"),
+    ///         Subvolume {
+    ///             mount_options: vec![String::from("noatime")],
+    ///             ..Subvolume::new("/nix", "/nix")
+    ///         },
+    ///     ],
+    /// });
+    ///
+    /// let toks = quote!($content);
+    ///
+    /// assert_eq!(
+    ///     vec![
+    ///         "{",
+    ///         "    ...",
+    ///         "}:",
+    ///         "",
+    ///         "{",
+    ///         "    type = \"luks\";",
+    ///         "    name = \"crypted\";",
+    ///         "    content = {",
+    ///         "        type = \"btrfs\";",
+    ///         "        subvolumes = {",
+    ///         "            \"/root\" = {",
+    ///         "                mountpoint = \"/\";",
+    ///         "            };",
+    ///         "            \"/nix\" = {",
+    ///         "                mountpoint = \"/nix\";",
+    ///         "                mountOptions = [",
+    ///         "                    \"noatime\"",
+    ///         "                ];",
+    ///         "            };",
+    ///         "        };",
+    ///         "    };",
+    ///         "}",
+    ///     ],
+    ///     toks.to_file_vec()?
+    /// );
+    /// # Ok::<_, genco::fmt::Error>(())
+    /// ```
+    fn format_into(self, tokens: &mut Tokens<Nix>) {
+        tokens.append("{");
+        tokens.indent();
+        match self {
+            Content::Gpt(partitions) => {
+                let partitions: Vec<(String, Partition)> = partitions
+                    .into_iter()
+                    .map(|partition| (partition.name.clone(), partition))
+                    .collect();
+                quote_in! { *tokens =>
+                    type = "gpt";
+                    partitions = $(attrs(partitions));
+                }
+            }
+            Content::Filesystem {
+                format,
+                mountpoint,
+                mount_options,
+            } => {
+                quote_in! { *tokens =>
+                    type = "filesystem";
+                    format = $(quoted(format));
+                    $(if let Some(mountpoint) = mountpoint {
+                        mountpoint = $(quoted(mountpoint));
+                    })
+                    $(if !mount_options.is_empty() {
+                        mountOptions = $(strings(mount_options));
+                    })
+                }
+            }
+            Content::Swap { random_encryption } => {
+                quote_in! { *tokens =>
+                    type = "swap";
+                    $(if random_encryption {
+                        randomEncryption = true;
+                    })
+                }
+            }
+            Content::Luks { name, content } => {
+                quote_in! { *tokens =>
+                    type = "luks";
+                    name = $(quoted(name));
+                    content = $(*content);
+                }
+            }
+            Content::LvmPv { vg } => {
+                quote_in! { *tokens =>
+                    type = "lvm_pv";
+                    vg = $(quoted(vg));
+                }
+            }
+            Content::Btrfs {
+                mountpoint,
+                subvolumes,
+            } => {
+                let subvolumes: Vec<(String, Subvolume)> = subvolumes
+                    .into_iter()
+                    .map(|subvolume| (subvolume.name.clone(), subvolume))
+                    .collect();
+                quote_in! { *tokens =>
+                    type = "btrfs";
+                    $(if let Some(mountpoint) = mountpoint {
+                        mountpoint = $(quoted(mountpoint));
+                    })
+                    $(if !subvolumes.is_empty() {
+                        subvolumes = $(attrs(subvolumes));
+                    })
+                }
+            }
+            Content::Zfs { pool } => {
+                quote_in! { *tokens =>
+                    type = "zfs";
+                    pool = $(quoted(pool));
+                }
+            }
+            Content::Mdraid { name } => {
+                quote_in! { *tokens =>
+                    type = "mdraid";
+                    name = $(quoted(name));
+                }
+            }
+        }
+        tokens.unindent();
+        tokens.append("}");
+    }
+}
+
+pub struct Partition {
+    pub content: Option<Content>,
+    /// Name do **not** included in quote.
+    pub name: String,
+    /// Size such as `512M` or `100%`.
+    pub size: String,
+    /// GPT type code such as `EF00` or `EF02`.
+    pub part_type: Option<String>,
+}
+
+impl Partition {
+    pub fn new(name: &str, size: &str, content: Content) -> Self {
+        Self {
+            content: Some(content),
+            name: String::from(name),
+            size: String::from(size),
+            part_type: None,
+        }
+    }
+
+    /// EFI system partition mounted at `/boot`.
+    pub fn esp(size: &str) -> Self {
+        Self {
+            part_type: Some(String::from("EF00")),
+            ..Self::new("ESP", size, Content::filesystem("vfat", "/boot"))
+        }
+    }
+
+    /// BIOS boot partition for GRUB on GPT.
+    pub fn bios_boot() -> Self {
+        Self {
+            content: None,
+            name: String::from("boot"),
+            size: String::from("1M"),
+            part_type: Some(String::from("EF02")),
+        }
+    }
+}
+
+impl FormatInto<Nix> for Partition {
+    fn format_into(self, tokens: &mut Tokens<Nix>) {
+        quote_in! { *tokens =>
+            {
+                size = $(quoted(self.size));
+                $(if let Some(part_type) = self.part_type {
+                    type = $(quoted(part_type));
+                })
+                $(if let Some(content) = self.content {
+                    content = $content;
+                })
+            }
+        }
+    }
+}
+
+pub struct Subvolume {
+    pub mountpoint: Option<String>,
+    pub mount_options: Vec<String>,
+    /// Name do **not** included in quote.
+    pub name: String,
+}
+
+impl Subvolume {
+    pub fn new(name: &str, mountpoint: &str) -> Self {
+        Self {
+            mountpoint: Some(String::from(mountpoint)),
+            mount_options: Vec::new(),
+            name: String::from(name),
+        }
+    }
+}
+
+impl FormatInto<Nix> for Subvolume {
+    fn format_into(self, tokens: &mut Tokens<Nix>) {
+        quote_in! { *tokens =>
+            {
+                $(if let Some(mountpoint) = self.mountpoint {
+                    mountpoint = $(quoted(mountpoint));
+                })
+                $(if !self.mount_options.is_empty() {
+                    mountOptions = $(strings(self.mount_options));
+                })
+            }
+        }
+    }
+}
+
+pub struct Disk {
+    pub content: Content,
+    /// Device path such as `/dev/disk/by-id/nvme-...`.
+    pub device: String,
+    /// Name do **not** included in quote.
+    pub name: String,
+}
+
+impl Disk {
+    pub fn new(name: &str, device: &str, content: Content) -> Self {
+        Self {
+            content,
+            device: String::from(device),
+            name: String::from(name),
+        }
+    }
+}
+
+impl FormatInto<Nix> for Disk {
+    fn format_into(self, tokens: &mut Tokens<Nix>) {
+        quote_in! { *tokens =>
+            {
+                type = "disk";
+                device = $(quoted(self.device));
+                content = $(self.content);
+            }
+        }
+    }
+}
+
+/// mdadm array assembled from `Content::Mdraid` members.
+pub struct Mdadm {
+    pub content: Content,
+    pub level: u32,
+    /// Name do **not** included in quote.
+    pub name: String,
+}
+
+impl FormatInto<Nix> for Mdadm {
+    fn format_into(self, tokens: &mut Tokens<Nix>) {
+        quote_in! { *tokens =>
+            {
+                type = "mdadm";
+                level = $(self.level.to_string());
+                content = $(self.content);
+            }
+        }
+    }
+}
+
+pub struct LogicalVolume {
+    pub content: Content,
+    /// Name do **not** included in quote.
+    pub name: String,
+    /// Size such as `20G` or `100%FREE`.
+    pub size: String,
+}
+
+impl FormatInto<Nix> for LogicalVolume {
+    fn format_into(self, tokens: &mut Tokens<Nix>) {
+        quote_in! { *tokens =>
+            {
+                size = $(quoted(self.size));
+                content = $(self.content);
+            }
+        }
+    }
+}
+
+/// LVM volume group built from `Content::LvmPv` members.
+pub struct VolumeGroup {
+    pub lvs: Vec<LogicalVolume>,
+    /// Name do **not** included in quote.
+    pub name: String,
+}
+
+impl FormatInto<Nix> for VolumeGroup {
+    fn format_into(self, tokens: &mut Tokens<Nix>) {
+        let lvs: Vec<(String, LogicalVolume)> = self
+            .lvs
+            .into_iter()
+            .map(|lv| (lv.name.clone(), lv))
+            .collect();
+        quote_in! { *tokens =>
+            {
+                type = "lvm_vg";
+                lvs = $(attrs(lvs));
+            }
+        }
+    }
+}
+
+pub struct Dataset {
+    pub mountpoint: Option<String>,
+    /// Name do **not** included in quote, e.g. `root/nix`.
+    pub name: String,
+    /// ZFS properties such as `compression`.
+    pub options: Vec<(String, String)>,
+}
+
+impl FormatInto<Nix> for Dataset {
+    fn format_into(self, tokens: &mut Tokens<Nix>) {
+        quote_in! { *tokens =>
+            {
+                type = "zfs_fs";
+                $(if let Some(mountpoint) = self.mountpoint {
+                    mountpoint = $(quoted(mountpoint));
+                })
+                $(if !self.options.is_empty() {
+                    options = $(options(self.options));
+                })
+            }
+        }
+    }
+}
+
+/// ZFS pool built from `Content::Zfs` members.
+pub struct Zpool {
+    pub datasets: Vec<Dataset>,
+    /// `mirror`, `raidz`, ... or empty for a stripe.
+    pub mode: String,
+    pub mountpoint: Option<String>,
+    /// Name do **not** included in quote.
+    pub name: String,
+    /// Properties of the root dataset.
+    pub root_fs_options: Vec<(String, String)>,
+}
+
+impl FormatInto<Nix> for Zpool {
+    fn format_into(self, tokens: &mut Tokens<Nix>) {
+        let datasets: Vec<(String, Dataset)> = self
+            .datasets
+            .into_iter()
+            .map(|dataset| (dataset.name.clone(), dataset))
+            .collect();
+        quote_in! { *tokens =>
+            {
+                type = "zpool";
+                mode = $(quoted(self.mode));
+                $(if !self.root_fs_options.is_empty() {
+                    rootFsOptions = $(options(self.root_fs_options));
+                })
+                $(if let Some(mountpoint) = self.mountpoint {
+                    mountpoint = $(quoted(mountpoint));
+                })
+                $(if !datasets.is_empty() {
+                    datasets = $(attrs(datasets));
+                })
+            }
+        }
+    }
+}
+
+/// The disko layout of one host.
+pub struct DiskoConfiguration {
+    pub disks: Vec<Disk>,
+    pub mdadm: Vec<Mdadm>,
+    pub lvm_vgs: Vec<VolumeGroup>,
+    /// Name do **not** included in quote.
+    pub name: String,
+    /// Require an EFI system partition.
+    pub uefi: bool,
+    pub zpools: Vec<Zpool>,
+}
+
+impl DiskoConfiguration {
+    /// An empty UEFI layout.
+    pub fn new(name: &str) -> Self {
+        Self {
+            disks: Vec::new(),
+            mdadm: Vec::new(),
+            lvm_vgs: Vec::new(),
+            name: String::from(name),
+            uefi: true,
+            zpools: Vec::new(),
+        }
+    }
+
+    /// The common single disk layout: an ESP and an ext4 root.
+    ///
+    /// ```
+    /// use genco::prelude::*;
+    /// use honey::hive::*;
+    ///
+    /// let disko_configuration = DiskoConfiguration::new1("machine1", "/dev/sda", "512M");
+    ///
+    /// let toks = quote!($disko_configuration);
+    ///
+    /// assert_eq!(
+    ///     vec![
+    ///         "{",
+    ///         "    ...",
+    ///         "}:",
+    ///         "",
+    ///         "{",
+    ///         "    disko.devices = {",
+    ///         "        disk = {",
+    ///         "            main = {",
+    ///         "                type = \"disk\";",
+    ///         "                device = \"/dev/sda\";",
+    ///         "                content = {",
+    ///         "                    type = \"gpt\";",
+    ///         "                    partitions = {",
+    ///         "                        ESP = {",
+    ///         "                            size = \"512M\";",
+    ///         "                            type = \"EF00\";",
+    ///         "                            content = {",
+    ///         "                                type = \"filesystem\";",
+    ///         "                                format = \"vfat\";",
+    ///         "                                mountpoint = \"/boot\";",
+    ///         "                            };",
+    ///         "                        };",
+    ///         "                        root = {",
+    ///         "                            size = \"100%\";",
+    ///         "                            content = {",
+    ///         "                                type = \"filesystem\";",
+    ///         "                                format = \"ext4\";",
+    ///         "                                mountpoint = \"/\";",
+    ///         "                            };",
+    ///         "                        };",
+    ///         "                    };",
+    ///         "                };",
+    ///         "            };",
+    ///         "        };",
+    ///         "    };",
+    ///         "}",
+    ///     ],
+    ///     toks.to_file_vec()?
+    /// );
+    /// # Ok::<_, genco::fmt::Error>(())
+    /// ```
+    pub fn new1(name: &str, device: &str, esp_size: &str) -> Self {
+        let mut disko_configuration = Self::new(name);
+        disko_configuration.disks.push(Disk::new(
+            "main",
+            device,
+            Content::Gpt(vec![
+                Partition::esp(esp_size),
+                Partition::new("root", "100%", Content::filesystem("ext4", "/")),
+            ]),
+        ));
+        disko_configuration
+    }
+
+    /// Check the layout before any Nix is written.
+    ///
+    /// ```
+    /// use honey::hive::*;
+    /// use honey::hive::disko_configurations::Error;
+    ///
+    /// let mut disko_configuration = DiskoConfiguration::new1("machine1", "/dev/sda", "512M");
+    /// assert_eq!(Ok(()), disko_configuration.validate());
+    ///
+    /// disko_configuration.disks.push(Disk::new("data", "/dev/sdb", Content::filesystem("xfs", "/")));
+    /// assert_eq!(
+    ///     Err(Error::DuplicateMountpoint(String::from("machine1"), String::from("/"))),
+    ///     disko_configuration.validate()
+    /// );
+    ///
+    /// let mut disko_configuration = DiskoConfiguration::new("machine2");
+    /// disko_configuration.disks.push(Disk::new("main", "/dev/sda", Content::Gpt(vec![
+    ///     Partition::new("root", "100%", Content::filesystem("ext4", "/")),
+    /// ])));
+    /// assert_eq!(
+    ///     "host `machine2`: UEFI boot needs an EF00 partition",
+    ///     disko_configuration.validate().unwrap_err().to_string()
+    /// );
+    ///
+    /// disko_configuration.disks[0].content = Content::Gpt(vec![
+    ///     Partition::esp("512M"),
+    ///     Partition::new("pv", "100%", Content::LvmPv { vg: String::from("pool") }),
+    /// ]);
+    /// assert_eq!(
+    ///     "host `machine2`: unknown device `pool`",
+    ///     disko_configuration.validate().unwrap_err().to_string()
+    /// );
+    /// ```
+    pub fn validate(&self) -> Result<(), Error> {
+        let host = || self.name.clone();
+        let mut names = BTreeSet::new();
+        let mut mountpoints = BTreeSet::new();
+        let mut mount = |mountpoint: &Option<String>| match mountpoint {
+            Some(mountpoint) if !mountpoints.insert(mountpoint.clone()) => Err(
+                Error::DuplicateMountpoint(self.name.clone(), mountpoint.clone()),
+            ),
+            _ => Ok(()),
+        };
+
+        // Contents placed directly on a device, and whether a GPT table may
+        // sit there.
+        let mut roots = Vec::new();
+        for disk in &self.disks {
+            if !names.insert(format!("disk.{}", disk.name)) {
+                return Err(Error::DuplicateName(host(), disk.name.clone()));
+            }
+            roots.push((&disk.content, true));
+        }
+        for array in &self.mdadm {
+            if !names.insert(format!("mdadm.{}", array.name)) {
+                return Err(Error::DuplicateName(host(), array.name.clone()));
+            }
+            roots.push((&array.content, true));
+        }
+        for vg in &self.lvm_vgs {
+            if !names.insert(format!("lvm_vg.{}", vg.name)) {
+                return Err(Error::DuplicateName(host(), vg.name.clone()));
+            }
+            let mut lvs = BTreeSet::new();
+            for lv in &vg.lvs {
+                if !lvs.insert(&lv.name) {
+                    return Err(Error::DuplicateName(host(), lv.name.clone()));
+                }
+                roots.push((&lv.content, false));
+            }
+        }
+
+        let mut contents = Vec::new();
+        for (root, gpt) in roots {
+            let start = contents.len();
+            root.walk(&mut contents);
+            let skip = if gpt { start + 1 } else { start };
+            if contents[skip..]
+                .iter()
+                .any(|content| matches!(content, Content::Gpt(_)))
+            {
+                return Err(Error::MisplacedGpt(host()));
+            }
+        }
+
+        let mut esp = false;
+        for content in &contents {
+            match content {
+                Content::Gpt(partitions) => {
+                    let mut partition_names = BTreeSet::new();
+                    for partition in partitions {
+                        if !partition_names.insert(&partition.name) {
+                            return Err(Error::DuplicateName(host(), partition.name.clone()));
+                        }
+                        esp |= partition.part_type.as_deref() == Some("EF00");
+                    }
+                }
+                Content::Filesystem { mountpoint, .. } => mount(mountpoint)?,
+                Content::Btrfs {
+                    mountpoint,
+                    subvolumes,
+                } => {
+                    mount(mountpoint)?;
+                    let mut subvolume_names = BTreeSet::new();
+                    for subvolume in subvolumes {
+                        if !subvolume_names.insert(&subvolume.name) {
+                            return Err(Error::DuplicateName(host(), subvolume.name.clone()));
+                        }
+                        mount(&subvolume.mountpoint)?;
+                    }
+                }
+                Content::LvmPv { vg } if !self.lvm_vgs.iter().any(|x| &x.name == vg) => {
+                    return Err(Error::UnknownDevice(host(), vg.clone()));
+                }
+                Content::Zfs { pool } if !self.zpools.iter().any(|x| &x.name == pool) => {
+                    return Err(Error::UnknownDevice(host(), pool.clone()));
+                }
+                Content::Mdraid { name } if !self.mdadm.iter().any(|x| &x.name == name) => {
+                    return Err(Error::UnknownDevice(host(), name.clone()));
+                }
+                _ => {}
+            }
+        }
+
+        for zpool in &self.zpools {
+            if !names.insert(format!("zpool.{}", zpool.name)) {
+                return Err(Error::DuplicateName(host(), zpool.name.clone()));
+            }
+            mount(&zpool.mountpoint)?;
+            let mut datasets = BTreeSet::new();
+            for dataset in &zpool.datasets {
+                if !datasets.insert(&dataset.name) {
+                    return Err(Error::DuplicateName(host(), dataset.name.clone()));
+                }
+                mount(&dataset.mountpoint)?;
+            }
+        }
+
+        if self.uefi && !esp {
+            return Err(Error::MissingEsp(host()));
+        }
+        Ok(())
+    }
+}
+
+impl FormatInto<Nix> for DiskoConfiguration {
+    fn format_into(self, tokens: &mut Tokens<Nix>) {
+        fn named<T>(items: Vec<T>, name: fn(&T) -> String) -> Vec<(String, T)> {
+            items.into_iter().map(|item| (name(&item), item)).collect()
+        }
+        let disks = named(self.disks, |disk| disk.name.clone());
+        let mdadm = named(self.mdadm, |array| array.name.clone());
+        let lvm_vgs = named(self.lvm_vgs, |vg| vg.name.clone());
+        let zpools = named(self.zpools, |zpool| zpool.name.clone());
+        quote_in! { *tokens =>
+            {
+                disko.devices = {
+                    $(if !disks.is_empty() {
+                        disk = $(attrs(disks));
+                    })
+                    $(if !mdadm.is_empty() {
+                        mdadm = $(attrs(mdadm));
+                    })
+                    $(if !lvm_vgs.is_empty() {
+                        lvm_vg = $(attrs(lvm_vgs));
+                    })
+                    $(if !zpools.is_empty() {
+                        zpool = $(attrs(zpools));
+                    })
+                };
+            }
+        }
+    }
+}
+
+pub struct DiskoConfigurations(pub Vec<DiskoConfiguration>);
+
+impl DiskoConfigurations {
+    /// Validate every layout, so that an invalid one never reaches disk.
+    ///
+    /// ```
+    /// use honey::hive::*;
+    ///
+    /// let mut bios = DiskoConfiguration::new("machine2");
+    /// bios.uefi = false;
+    /// bios.disks.push(Disk::new("main", "/dev/vda", Content::Gpt(vec![
+    ///     Partition::bios_boot(),
+    ///     Partition::new("root", "100%", Content::filesystem("ext4", "/")),
+    /// ])));
+    ///
+    /// let disko_configurations = DiskoConfigurations::new(vec![
+    ///     DiskoConfiguration::new1("machine1", "/dev/sda", "512M"),
+    ///     bios,
+    /// ]);
+    ///
+    /// assert!(disko_configurations.is_ok());
+    /// ```
+    pub fn new(disko_configurations: Vec<DiskoConfiguration>) -> Result<Self, Error> {
+        let mut names = BTreeSet::new();
+        for disko_configuration in &disko_configurations {
+            if !names.insert(&disko_configuration.name) {
+                return Err(Error::DuplicateName(
+                    disko_configuration.name.clone(),
+                    disko_configuration.name.clone(),
+                ));
+            }
+            disko_configuration.validate()?;
+        }
+        Ok(Self(disko_configurations))
+    }
+}
+
+/// Plain data, referring to no input.
+impl Inputs for DiskoConfigurations {
+    fn inputs(&self, _: &mut BTreeSet<String>) {}
+}
+
+impl Inherits for DiskoConfigurations {
+    fn inherits(&self, _: &mut Inherited) {}
+}
+
+impl FormatInto<Nix> for DiskoConfigurations {
+    fn format_into(self, tokens: &mut Tokens<Nix>) {
+        tokens.append("{");
+        tokens.indent();
+        for disko_configuration in self.0 {
+            quote_in!(*tokens => $(attr_name(&disko_configuration.name)) = $disko_configuration;);
+            tokens.push();
+        }
+        tokens.unindent();
+        tokens.append("}");
+    }
+}
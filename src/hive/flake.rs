@@ -0,0 +1,332 @@
+use crate::hive::cell::{self, Cell, File};
+use crate::hive::registry::{self, Registry};
+use crate::nix::value::StringPart;
+use crate::nix::NixValue;
+use genco::prelude::*;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::path::Path;
+
+/// Error returned when the inputs of a cell can not be collected.
+#[derive(Debug)]
+pub enum Error {
+    /// A cell refers to an input the registry does not know.
+    Input(registry::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Input(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Input(error) => Some(error),
+        }
+    }
+}
+
+impl From<registry::Error> for Error {
+    fn from(error: registry::Error) -> Self {
+        Error::Input(error)
+    }
+}
+
+/// Flake inputs a block refers to, through `inherit (inputs) X;` or a
+/// select of `inputs.X`. `inputs.cells` holds cells, not inputs.
+///
+/// ```
+/// use honey::hive::*;
+/// use honey::hive::flake::Inputs;
+/// use honey::nix::NixValue;
+/// use std::collections::BTreeSet;
+///
+/// let configurations = Configurations::builder("machine00")
+///     .bee(Import::bee1("machine00", None, "nixos-23-05", "x86_64-linux")?)
+///     .import(Import::new1("inputs.nixos-hardware.nixosModules.raspberry-pi-4"))
+///     .import(Import::cells("shared", "nixosProfiles", "base"))
+///     .build();
+///
+/// let mut inputs = BTreeSet::new();
+/// configurations.inputs(&mut inputs);
+///
+/// assert_eq!(vec!["disko", "nixos-23-05", "nixos-hardware"], inputs.into_iter().collect::<Vec<_>>());
+/// # Ok::<_, honey::hive::bee::Error>(())
+/// ```
+pub trait Inputs {
+    fn inputs(&self, names: &mut BTreeSet<String>);
+}
+
+impl Inputs for NixValue {
+    fn inputs(&self, names: &mut BTreeSet<String>) {
+        match self {
+            NixValue::Inherit { path, name } => {
+                let mut path = path.split('.');
+                if path.next() == Some("inputs") {
+                    match path.next() {
+                        None => {
+                            names.insert(name.clone());
+                        }
+                        Some("cells") => (),
+                        Some(input) => {
+                            names.insert(input.to_string());
+                        }
+                    }
+                }
+            }
+            NixValue::Select { value, path } => match (&**value, path.first()) {
+                (NixValue::Argument(argument), Some(input))
+                    if argument == "inputs" && input != "cells" =>
+                {
+                    names.insert(input.clone());
+                }
+                (value, _) => value.inputs(names),
+            },
+            NixValue::String(parts) => {
+                for part in parts {
+                    if let StringPart::Interpolation(value) = part {
+                        value.inputs(names);
+                    }
+                }
+            }
+            NixValue::List(values) => {
+                for value in values {
+                    value.inputs(names);
+                }
+            }
+            NixValue::AttrSet(attrs) | NixValue::RecAttrSet(attrs) => {
+                for (_, value) in attrs {
+                    value.inputs(names);
+                }
+            }
+            NixValue::Variable { value, .. } | NixValue::Lambda { body: value, .. } => {
+                value.inputs(names)
+            }
+            NixValue::Let { bindings, body } => {
+                for (_, value) in bindings {
+                    value.inputs(names);
+                }
+                body.inputs(names);
+            }
+            NixValue::With { namespace, body } => {
+                namespace.inputs(names);
+                body.inputs(names);
+            }
+            NixValue::Apply { function, argument } => {
+                function.inputs(names);
+                argument.inputs(names);
+            }
+            NixValue::Null
+            | NixValue::Bool(_)
+            | NixValue::Int(_)
+            | NixValue::Float(_)
+            | NixValue::Path(_)
+            | NixValue::Ident(_)
+            | NixValue::Argument(_)
+            | NixValue::Tokens(_) => (),
+        }
+    }
+}
+
+/// Block types provided by hive, collected into flake outputs. The other
+/// blocks are plain `functions` from std, unless in `STD_BLOCKS`.
+const HIVE_BLOCKS: [(&str, &str); 5] = [
+    ("colmenaConfigurations", "colmenaHive"),
+    ("darwinConfigurations", "darwinConfigurations"),
+    ("diskoConfigurations", "diskoConfigurations"),
+    ("homeConfigurations", "homeConfigurations"),
+    ("nixosConfigurations", "nixosConfigurations"),
+];
+
+/// Block types provided by std besides `functions`.
+const STD_BLOCKS: [&str; 1] = ["devshells"];
+
+pub struct Input {
+    pub name: String,
+    pub url: String,
+    /// `inputs.<input>.follows = "<target>"`, only for targets that are
+    /// inputs of the flake.
+    pub follows: BTreeMap<String, String>,
+}
+
+/// The `flake.nix` growing the hive.
+pub struct Flake {
+    /// Cell block names, in order of first use.
+    pub blocks: Vec<String>,
+    /// Sorted, `hive` and `std` included.
+    pub inputs: Vec<Input>,
+}
+
+impl Flake {
+    /// Collect the inputs the blocks of the cells refer to, see `Inputs`, and
+    /// the blocks in use. Inputs are resolved through the `registry`, so
+    /// a misspelled input name fails here rather than in `nix flake lock`.
+    ///
+    /// ```
+    /// use honey::hive::*;
+    /// use honey::hive::cell::{self, Cell};
+    /// use honey::hive::flake::Flake;
+    /// use honey::hive::registry::Registry;
+    /// use std::path::Path;
+    ///
+    /// let mut cell = Cell::new("hosts");
+    /// cell.block("nixosConfigurations", NixosConfigurations::new1("machine", 1, Some("home-23-05"), "nixos-23-05", "x86_64-linux")?);
+    /// cell.block("hardwareProfiles", NixosConfigurations(vec![Configurations::new("machine00", vec![])]));
+    ///
+    /// let flake = Flake::new(&[cell], &Registry::default())?;
+    ///
+    /// assert_eq!(
+    ///     cell::header() + "{
+    ///     inputs = {
+    ///         disko.url = \"github:nix-community/disko\";
+    ///         hive.url = \"github:divnix/hive\";
+    ///         home-23-05 = {
+    ///             url = \"github:nix-community/home-manager/release-23.05\";
+    ///             inputs.nixpkgs.follows = \"nixos-23-05\";
+    ///         };
+    ///         nixos-23-05.url = \"github:NixOS/nixpkgs/nixos-23.05\";
+    ///         std.url = \"github:divnix/std\";
+    ///     };
+    ///
+    ///     outputs = {
+    ///         hive,
+    ///         self,
+    ///         std,
+    ///         ...
+    ///     } @ inputs:
+    ///         hive.growOn {
+    ///             inherit inputs;
+    ///             cellsFrom = ./cells;
+    ///             cellBlocks = with std.blockTypes; with hive.blockTypes; [
+    ///                 nixosConfigurations
+    ///                 (functions \"hardwareProfiles\")
+    ///             ];
+    ///         }
+    ///         {
+    ///             nixosConfigurations = hive.collect self \"nixosConfigurations\";
+    ///         };
+    /// }
+    /// ",
+    ///     flake.file(Path::new("."))?.contents
+    /// );
+    /// # Ok::<_, Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn new(cells: &[Cell], registry: &Registry) -> Result<Self, Error> {
+        let mut blocks: Vec<String> = Vec::new();
+        let mut names = BTreeSet::from([String::from("hive"), String::from("std")]);
+        for cell in cells {
+            for block in &cell.blocks {
+                if !blocks.contains(&block.name) {
+                    blocks.push(block.name.clone());
+                }
+                names.extend(block.inputs.iter().cloned());
+            }
+        }
+        let mut inputs = Vec::new();
+        for name in &names {
+            let entry = registry.resolve(name)?;
+            inputs.push(Input {
+                name: name.clone(),
+                url: entry.url,
+                follows: entry
+                    .follows
+                    .into_iter()
+                    .filter(|(_, target)| names.contains(target))
+                    .collect(),
+            });
+        }
+        Ok(Self { blocks, inputs })
+    }
+
+    /// Render `flake.nix` below the hive `root`.
+    pub fn file(self, root: &Path) -> genco::fmt::Result<File> {
+        let flake = self;
+        let tokens = quote!($flake);
+        Ok(File {
+            path: root.join("flake.nix"),
+            contents: cell::header() + &cell::render(&tokens, true)?,
+        })
+    }
+}
+
+impl FormatInto<Nix> for Flake {
+    fn format_into(self, tokens: &mut Tokens<Nix>) {
+        let hive_block = |block: &str| HIVE_BLOCKS.iter().find(|(name, _)| *name == block);
+        tokens.append("{");
+        tokens.indent();
+
+        tokens.append("inputs = {");
+        tokens.indent();
+        for input in self.inputs {
+            if input.follows.is_empty() {
+                quote_in!(*tokens => $(input.name).url = $(quoted(input.url)););
+            } else {
+                quote_in! { *tokens =>
+                    $(input.name) = {
+                        url = $(quoted(input.url));
+                        $(for (follows, target) in input.follows {
+                            inputs.$follows.follows = $(quoted(target));$['\r']
+                        })
+                    };
+                }
+            }
+            tokens.push();
+        }
+        tokens.unindent();
+        tokens.append("};");
+        tokens.line();
+
+        tokens.append("outputs = {");
+        tokens.indent();
+        for argument in ["hive,", "self,", "std,", "..."] {
+            tokens.append(argument);
+            tokens.push();
+        }
+        tokens.unindent();
+        tokens.append("} @ inputs:");
+        tokens.indent();
+
+        tokens.append("hive.growOn {");
+        tokens.indent();
+        tokens.append("inherit inputs;");
+        tokens.push();
+        tokens.append("cellsFrom = ./cells;");
+        tokens.push();
+        tokens.append("cellBlocks = with std.blockTypes; with hive.blockTypes; [");
+        tokens.indent();
+        for block in &self.blocks {
+            match hive_block(block) {
+                Some(_) => tokens.append(block.clone()),
+                None if STD_BLOCKS.contains(&block.as_str()) => {
+                    quote_in!(*tokens => ($(block.clone()) $(quoted(block.clone()))))
+                }
+                None => quote_in!(*tokens => (functions $(quoted(block.clone())))),
+            }
+            tokens.push();
+        }
+        tokens.unindent();
+        tokens.append("];");
+        tokens.unindent();
+        tokens.append("}");
+        tokens.push();
+
+        tokens.append("{");
+        tokens.indent();
+        for block in &self.blocks {
+            if let Some((name, output)) = hive_block(block) {
+                quote_in!(*tokens => $(*output) = hive.collect self $(quoted(*name)););
+                tokens.push();
+            }
+        }
+        tokens.unindent();
+        tokens.append("};");
+        tokens.unindent();
+
+        tokens.unindent();
+        tokens.append("}");
+    }
+}
@@ -0,0 +1,179 @@
+use crate::hive::*;
+
+/// Per-host blocks of a fleet that share their configuration through a
+/// group of another cell: every host of `diskoConfigurations`,
+/// `hardwareProfiles`, `nixosModules` and `nixosProfiles` imports
+/// `inputs.cells.<cell>.<block>.<group>`.
+///
+/// ```
+/// use genco::prelude::*;
+/// use honey::hive::*;
+///
+/// let template = GroupTemplate::new1("machine", 2, "group", "myGroup")
+///     .group(TemplateBlock::Hardware, "raspberryPi")
+///     .group1(TemplateBlock::Profiles, "profiles", "server");
+///
+/// let mut blocks = template.blocks();
+/// let names: Vec<&str> = blocks.iter().map(|(name, _)| name.as_str()).collect();
+///
+/// assert_eq!(
+///     vec!["diskoConfigurations", "hardwareProfiles", "nixosModules", "nixosProfiles"],
+///     names
+/// );
+///
+/// let (_, nixos_profiles) = blocks.remove(3);
+/// let (_, hardware_profiles) = blocks.remove(1);
+///
+/// assert_eq!(
+///     vec![
+///         "{",
+///         "    inputs,",
+///         "    ...",
+///         "}:",
+///         "",
+///         "let",
+///         "    inherit (inputs.cells) group;",
+///         "in",
+///         "",
+///         "{",
+///         "    machine00 = {",
+///         "        imports = [",
+///         "            group.hardwareProfiles.raspberryPi",
+///         "        ];",
+///         "    };",
+///         "    machine01 = {",
+///         "        imports = [",
+///         "            group.hardwareProfiles.raspberryPi",
+///         "        ];",
+///         "    };",
+///         "}",
+///     ],
+///     quote!($hardware_profiles).to_file_vec()?
+/// );
+///
+/// let nixos_profiles = quote!($nixos_profiles).to_file_string()?;
+///
+/// assert!(nixos_profiles.contains("    inherit (inputs.cells) profiles;\n"));
+/// assert!(nixos_profiles.contains("            profiles.nixosProfiles.server\n"));
+/// # Ok::<_, genco::fmt::Error>(())
+/// ```
+pub struct GroupTemplate {
+    pub hosts: Vec<String>,
+    /// The group imported by each block, in order.
+    pub groups: Vec<BlockGroup>,
+}
+
+/// `inputs.cells.<cell>.<block>.<group>`, imported by every host of a
+/// `GroupTemplate` block.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlockGroup {
+    pub block: TemplateBlock,
+    pub cell: String,
+    pub group: String,
+}
+
+/// The per-host blocks a template writes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TemplateBlock {
+    Disko,
+    Hardware,
+    Modules,
+    Profiles,
+}
+
+impl TemplateBlock {
+    const ALL: [TemplateBlock; 4] = [
+        TemplateBlock::Disko,
+        TemplateBlock::Hardware,
+        TemplateBlock::Modules,
+        TemplateBlock::Profiles,
+    ];
+
+    /// Block name, e.g. `diskoConfigurations`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            TemplateBlock::Disko => "diskoConfigurations",
+            TemplateBlock::Hardware => "hardwareProfiles",
+            TemplateBlock::Modules => "nixosModules",
+            TemplateBlock::Profiles => "nixosProfiles",
+        }
+    }
+}
+
+impl GroupTemplate {
+    /// Every block of `hosts` imports `group` of `cell`.
+    pub fn new<T, S>(hosts: T, cell: &str, group: &str) -> Self
+    where
+        T: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            hosts: hosts.into_iter().map(Into::into).collect(),
+            groups: TemplateBlock::ALL
+                .iter()
+                .map(|block| BlockGroup {
+                    block: *block,
+                    cell: String::from(cell),
+                    group: String::from(group),
+                })
+                .collect(),
+        }
+    }
+
+    /// Like `new`, for the hosts `NixosConfigurations::new1` names from
+    /// `prefix` and `number`.
+    pub fn new1(prefix: &str, number: u32, cell: &str, group: &str) -> Self {
+        Self::new(HostPattern::new(prefix, number).names(), cell, group)
+    }
+
+    /// Like `new`, for hosts named by `naming`. Fails when a name appears
+    /// twice.
+    pub fn new2<T>(naming: T, cell: &str, group: &str) -> Result<Self, host_naming::Error>
+    where
+        T: Into<HostNaming>,
+    {
+        Ok(Self::new(naming.into().names()?, cell, group))
+    }
+
+    /// Import `group` of the same cell in `block`.
+    pub fn group(self, block: TemplateBlock, group: &str) -> Self {
+        let cell = self
+            .groups
+            .iter()
+            .find(|other| other.block == block)
+            .or(self.groups.first())
+            .map(|block_group| block_group.cell.clone())
+            .unwrap_or_default();
+        self.group1(block, &cell, group)
+    }
+
+    /// Import `group` of another `cell` in `block`. A block taken out of
+    /// `groups` is added back.
+    pub fn group1(mut self, block: TemplateBlock, cell: &str, group: &str) -> Self {
+        let block_group = BlockGroup {
+            block,
+            cell: String::from(cell),
+            group: String::from(group),
+        };
+        match self.groups.iter_mut().find(|other| other.block == block) {
+            Some(other) => *other = block_group,
+            None => self.groups.push(block_group),
+        }
+        self
+    }
+
+    /// Every block by name, ready for `Cell::block`.
+    pub fn blocks(&self) -> Vec<(String, NixosConfigurations)> {
+        let mut blocks = Vec::new();
+        for BlockGroup { block, cell, group } in &self.groups {
+            let name = block.name();
+            let configurations = self
+                .hosts
+                .iter()
+                .map(|host| Configurations::new(host, [Import::cells(cell, name, group)]))
+                .collect();
+            blocks.push((name.to_string(), NixosConfigurations(configurations)));
+        }
+        blocks
+    }
+}
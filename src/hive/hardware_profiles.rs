@@ -0,0 +1,523 @@
+use crate::hive::attrs::{attr_name, strings};
+use crate::hive::flake::Inputs;
+use crate::hive::inherit::{Inherited, Inherits};
+use crate::nix::{Binding, Expr};
+use genco::prelude::*;
+use genco::tokens::from_fn;
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Error returned when a hardware report can not be read.
+#[derive(Debug)]
+pub enum Error {
+    Io(PathBuf, io::Error),
+    Json(serde_json::Error),
+    /// The `nixos-generate-config` output is not valid Nix.
+    Syntax(crate::nix::Error),
+    /// The report is readable, but not something honey can represent.
+    Unsupported(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(path, error) => write!(f, "{}: {}", path.display(), error),
+            Error::Json(error) => write!(f, "{}", error),
+            Error::Syntax(error) => write!(f, "syntax error at {}", error),
+            Error::Unsupported(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(_, error) => Some(error),
+            Error::Json(error) => Some(error),
+            Error::Syntax(error) => Some(error),
+            Error::Unsupported(_) => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Microcode {
+    Amd,
+    Intel,
+}
+
+impl Microcode {
+    fn vendor(&self) -> &'static str {
+        match self {
+            Microcode::Amd => "amd",
+            Microcode::Intel => "intel",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileSystem {
+    pub device: String,
+    pub fs_type: String,
+    pub mountpoint: String,
+    pub options: Vec<String>,
+}
+
+/// The `hardwareProfiles` entry of one host.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HardwareProfile {
+    /// `boot.initrd.availableKernelModules`
+    pub available_kernel_modules: Vec<String>,
+    pub file_systems: Vec<FileSystem>,
+    /// `nixpkgs.hostPlatform`, e.g. `x86_64-linux`.
+    pub host_platform: Option<String>,
+    /// `boot.initrd.kernelModules`
+    pub initrd_kernel_modules: Vec<String>,
+    /// `boot.kernelModules`
+    pub kernel_modules: Vec<String>,
+    pub microcode: Option<Microcode>,
+    /// Name do **not** included in quote.
+    pub name: String,
+    pub swap_devices: Vec<String>,
+}
+
+/// Hardware classes of a nixos-facter report whose drivers are needed to
+/// reach the root filesystem.
+const FACTER_INITRD_CLASSES: [&str; 4] = [
+    "disk",
+    "firewire_controller",
+    "storage_controller",
+    "usb_controller",
+];
+
+impl HardwareProfile {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: String::from(name),
+            ..Default::default()
+        }
+    }
+
+    /// Read a `nixos-facter` report. The report holds no filesystems, those
+    /// come from `diskoConfigurations`.
+    ///
+    /// ```
+    /// use honey::hive::*;
+    ///
+    /// let hardware_profile = HardwareProfile::from_facter("machine1", r#"{
+    ///     "version": 1,
+    ///     "system": "x86_64-linux",
+    ///     "hardware": {
+    ///         "cpu": [{ "vendor_name": "GenuineIntel", "features": ["fpu", "vmx"] }],
+    ///         "storage_controller": [{ "driver_modules": ["ahci"] }, { "driver_modules": ["nvme"] }],
+    ///         "usb_controller": [{ "driver_modules": ["xhci_pci"] }],
+    ///         "network_controller": [{ "driver_modules": ["e1000e"] }]
+    ///     }
+    /// }"#)?;
+    ///
+    /// assert_eq!(vec!["ahci", "nvme", "xhci_pci"], hardware_profile.available_kernel_modules);
+    /// assert_eq!(vec!["kvm-intel"], hardware_profile.kernel_modules);
+    /// assert_eq!(Some(Microcode::Intel), hardware_profile.microcode);
+    /// assert_eq!(Some(String::from("x86_64-linux")), hardware_profile.host_platform);
+    /// # Ok::<_, honey::hive::hardware_profiles::Error>(())
+    /// ```
+    pub fn from_facter(name: &str, input: &str) -> Result<Self, Error> {
+        let report: Value = serde_json::from_str(input).map_err(Error::Json)?;
+        let mut hardware_profile = Self::new(name);
+        hardware_profile.host_platform = report["system"].as_str().map(String::from);
+
+        let hardware = &report["hardware"];
+        let mut modules = BTreeSet::new();
+        for class in FACTER_INITRD_CLASSES {
+            for device in hardware[class].as_array().into_iter().flatten() {
+                for module in device["driver_modules"].as_array().into_iter().flatten() {
+                    if let Some(module) = module.as_str() {
+                        modules.insert(module.to_string());
+                    }
+                }
+            }
+        }
+        hardware_profile.available_kernel_modules = modules.into_iter().collect();
+
+        if let Some(cpu) = hardware["cpu"].as_array().and_then(|cpus| cpus.first()) {
+            let features: Vec<&str> = cpu["features"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|feature| feature.as_str())
+                .collect();
+            match cpu["vendor_name"].as_str() {
+                Some("GenuineIntel") => {
+                    hardware_profile.microcode = Some(Microcode::Intel);
+                    if features.contains(&"vmx") {
+                        hardware_profile
+                            .kernel_modules
+                            .push(String::from("kvm-intel"));
+                    }
+                }
+                Some("AuthenticAMD") => {
+                    hardware_profile.microcode = Some(Microcode::Amd);
+                    if features.contains(&"svm") {
+                        hardware_profile
+                            .kernel_modules
+                            .push(String::from("kvm-amd"));
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(hardware_profile)
+    }
+
+    /// Read the output of `nixos-generate-config --show-hardware-config`.
+    /// Settings honey does not model, such as `networking.useDHCP`, are
+    /// skipped.
+    ///
+    /// ```
+    /// use honey::hive::*;
+    ///
+    /// let hardware_profile = HardwareProfile::from_nixos_generate_config("machine1", r#"
+    /// { config, lib, pkgs, modulesPath, ... }:
+    ///
+    /// {
+    ///   imports = [ (modulesPath + "/installer/scan/not-detected.nix") ];
+    ///
+    ///   boot.initrd.availableKernelModules = [ "xhci_pci" "nvme" ];
+    ///   boot.initrd.kernelModules = [ ];
+    ///   boot.kernelModules = [ "kvm-amd" ];
+    ///   boot.extraModulePackages = [ ];
+    ///
+    ///   fileSystems."/" =
+    ///     { device = "/dev/disk/by-uuid/0a1b";
+    ///       fsType = "ext4";
+    ///     };
+    ///
+    ///   fileSystems."/boot" =
+    ///     { device = "/dev/disk/by-uuid/2C3D";
+    ///       fsType = "vfat";
+    ///       options = [ "fmask=0022" "dmask=0022" ];
+    ///     };
+    ///
+    ///   swapDevices = [ { device = "/dev/disk/by-uuid/4e5f"; } ];
+    ///
+    ///   networking.useDHCP = lib.mkDefault true;
+    ///
+    ///   nixpkgs.hostPlatform = lib.mkDefault "x86_64-linux";
+    ///   hardware.cpu.amd.updateMicrocode = lib.mkDefault config.hardware.enableRedistributableFirmware;
+    /// }
+    /// "#)?;
+    ///
+    /// assert_eq!(vec!["xhci_pci", "nvme"], hardware_profile.available_kernel_modules);
+    /// assert_eq!(vec!["kvm-amd"], hardware_profile.kernel_modules);
+    /// assert_eq!(vec!["/", "/boot"], hardware_profile.file_systems.iter().map(|fs| fs.mountpoint.as_str()).collect::<Vec<_>>());
+    /// assert_eq!(vec!["fmask=0022", "dmask=0022"], hardware_profile.file_systems[1].options);
+    /// assert_eq!(vec!["/dev/disk/by-uuid/4e5f"], hardware_profile.swap_devices);
+    /// assert_eq!(Some(Microcode::Amd), hardware_profile.microcode);
+    /// assert_eq!(Some(String::from("x86_64-linux")), hardware_profile.host_platform);
+    /// # Ok::<_, honey::hive::hardware_profiles::Error>(())
+    /// ```
+    pub fn from_nixos_generate_config(name: &str, input: &str) -> Result<Self, Error> {
+        let body = match crate::nix::parse(input).map_err(Error::Syntax)? {
+            Expr::Lambda { body, .. } => *body,
+            body => body,
+        };
+        let bindings = match body {
+            Expr::AttrSet {
+                recursive: false,
+                bindings,
+            } => bindings,
+            body => return unsupported(format!("expected an attribute set, found `{}`", body)),
+        };
+        let mut attrs = Vec::new();
+        flatten(&bindings, &[], &mut attrs)?;
+
+        let mut hardware_profile = Self::new(name);
+        for (path, value) in attrs {
+            let path: Vec<&str> = path.iter().map(String::as_str).collect();
+            match path.as_slice() {
+                ["boot", "initrd", "availableKernelModules"] => {
+                    hardware_profile.available_kernel_modules = string_list(value)?
+                }
+                ["boot", "initrd", "kernelModules"] => {
+                    hardware_profile.initrd_kernel_modules = string_list(value)?
+                }
+                ["boot", "kernelModules"] => hardware_profile.kernel_modules = string_list(value)?,
+                ["fileSystems", mountpoint, attr] => {
+                    let index = match hardware_profile
+                        .file_systems
+                        .iter()
+                        .position(|fs| fs.mountpoint == *mountpoint)
+                    {
+                        Some(index) => index,
+                        None => {
+                            hardware_profile.file_systems.push(FileSystem {
+                                device: String::new(),
+                                fs_type: String::new(),
+                                mountpoint: mountpoint.to_string(),
+                                options: Vec::new(),
+                            });
+                            hardware_profile.file_systems.len() - 1
+                        }
+                    };
+                    let fs = &mut hardware_profile.file_systems[index];
+                    match *attr {
+                        "device" => fs.device = string(value)?,
+                        "fsType" => fs.fs_type = string(value)?,
+                        "options" => fs.options = string_list(value)?,
+                        _ => {}
+                    }
+                }
+                ["swapDevices"] => {
+                    let Expr::List(items) = value else {
+                        return unsupported(format!("expected a list, found `{}`", value));
+                    };
+                    for item in items {
+                        let mut attrs = Vec::new();
+                        if let Expr::AttrSet { bindings, .. } = item {
+                            flatten(bindings, &[], &mut attrs)?;
+                        }
+                        match attrs.iter().find(|(path, _)| path == &["device"]) {
+                            Some((_, device)) => {
+                                hardware_profile.swap_devices.push(string(device)?)
+                            }
+                            None => {
+                                return unsupported(format!(
+                                    "swap device without `device` in `{}`",
+                                    item
+                                ))
+                            }
+                        }
+                    }
+                }
+                ["nixpkgs", "hostPlatform"] => {
+                    hardware_profile.host_platform = Some(string(value)?)
+                }
+                ["hardware", "cpu", "amd", "updateMicrocode"] if !is_false(value) => {
+                    hardware_profile.microcode = Some(Microcode::Amd)
+                }
+                ["hardware", "cpu", "intel", "updateMicrocode"] if !is_false(value) => {
+                    hardware_profile.microcode = Some(Microcode::Intel)
+                }
+                _ => {}
+            }
+        }
+        Ok(hardware_profile)
+    }
+
+    /// Load a report, reading `.json` files as nixos-facter reports and
+    /// anything else as `nixos-generate-config` output.
+    pub fn load(name: &str, path: &Path) -> Result<Self, Error> {
+        let input =
+            std::fs::read_to_string(path).map_err(|error| Error::Io(path.to_path_buf(), error))?;
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("json") => Self::from_facter(name, &input),
+            _ => Self::from_nixos_generate_config(name, &input),
+        }
+    }
+}
+
+fn unsupported<T>(message: String) -> Result<T, Error> {
+    Err(Error::Unsupported(message))
+}
+
+/// Flatten nested attribute sets into `(path, value)` pairs.
+fn flatten<'a>(
+    bindings: &'a [Binding],
+    prefix: &[String],
+    out: &mut Vec<(Vec<String>, &'a Expr)>,
+) -> Result<(), Error> {
+    for binding in bindings {
+        let Binding::Attr { path, value } = binding else {
+            return unsupported(format!("unsupported `{}` in attribute set", binding));
+        };
+        let mut full = prefix.to_vec();
+        for name in path {
+            match name.as_str() {
+                Some(name) => full.push(name.to_string()),
+                None => {
+                    return unsupported(format!(
+                        "unsupported interpolated attribute name `{}`",
+                        name
+                    ))
+                }
+            }
+        }
+        match value {
+            Expr::AttrSet {
+                recursive: false,
+                bindings,
+            } => flatten(bindings, &full, out)?,
+            value => out.push((full, value)),
+        }
+    }
+    Ok(())
+}
+
+/// The value inside `lib.mkDefault value` or `lib.mkForce value`.
+fn unwrap_priority(expr: &Expr) -> &Expr {
+    match expr {
+        Expr::Apply { function, argument } => match function.attr_path().as_deref() {
+            Some(["lib", "mkDefault" | "mkForce"] | ["mkDefault" | "mkForce"]) => {
+                unwrap_priority(argument)
+            }
+            _ => expr,
+        },
+        expr => expr,
+    }
+}
+
+fn is_false(expr: &Expr) -> bool {
+    matches!(unwrap_priority(expr), Expr::Ident(ident) if ident == "false")
+}
+
+fn string(expr: &Expr) -> Result<String, Error> {
+    match unwrap_priority(expr) {
+        Expr::String(parts) => match parts.as_slice() {
+            [] => Ok(String::new()),
+            [crate::nix::StrPart::Literal(literal)] => Ok(literal.clone()),
+            _ => unsupported(format!("unsupported interpolated string `{}`", expr)),
+        },
+        _ => unsupported(format!("expected a string, found `{}`", expr)),
+    }
+}
+
+fn string_list(expr: &Expr) -> Result<Vec<String>, Error> {
+    match unwrap_priority(expr) {
+        Expr::List(items) => items.iter().map(string).collect(),
+        _ => unsupported(format!("expected a list of strings, found `{}`", expr)),
+    }
+}
+
+impl FormatInto<Nix> for HardwareProfile {
+    /// ```
+    /// use genco::prelude::*;
+    /// use honey::hive::*;
+    ///
+    /// let mut hardware_profile = HardwareProfile::new("machine1");
+    /// hardware_profile.available_kernel_modules = vec![String::from("nvme")];
+    /// hardware_profile.kernel_modules = vec![String::from("kvm-intel")];
+    /// hardware_profile.microcode = Some(Microcode::Intel);
+    /// hardware_profile.host_platform = Some(String::from("x86_64-linux"));
+    /// hardware_profile.file_systems.push(FileSystem {
+    ///     device: String::from("/dev/disk/by-label/nixos"),
+    ///     fs_type: String::from("ext4"),
+    ///     mountpoint: String::from("/"),
+    ///     options: Vec::new(),
+    /// });
+    /// hardware_profile.swap_devices.push(String::from("/dev/disk/by-label/swap"));
+    ///
+    /// let toks = quote!($hardware_profile);
+    ///
+    /// assert_eq!(
+    ///     vec![
+    ///         "{",
+    ///         "    ...",
+    ///         "}:",
+    ///         "",
+    ///         "{",
+    ///         "    boot.initrd.availableKernelModules = [",
+    ///         "        \"nvme\"",
+    ///         "    ];",
+    ///         "    boot.kernelModules = [",
+    ///         "        \"kvm-intel\"",
+    ///         "    ];",
+    ///         "    fileSystems.\"/\" = {",
+    ///         "        device = \"/dev/disk/by-label/nixos\";",
+    ///         "        fsType = \"ext4\";",
+    ///         "    };",
+    ///         "    swapDevices = [",
+    ///         "        {",
+    ///         "            device = \"/dev/disk/by-label/swap\";",
+    ///         "        }",
+    ///         "    ];",
+    ///         "    hardware.cpu.intel.updateMicrocode = true;",
+    ///         "    nixpkgs.hostPlatform = \"x86_64-linux\";",
+    ///         "}",
+    ///     ],
+    ///     toks.to_file_vec()?
+    /// );
+    /// # Ok::<_, genco::fmt::Error>(())
+    /// ```
+    fn format_into(self, tokens: &mut Tokens<Nix>) {
+        let file_systems = from_fn(move |tokens| {
+            for fs in self.file_systems {
+                quote_in! { *tokens =>
+                    fileSystems.$(attr_name(&fs.mountpoint)) = {
+                        device = $(quoted(fs.device));
+                        fsType = $(quoted(fs.fs_type));
+                        $(if !fs.options.is_empty() {
+                            options = $(strings(fs.options));
+                        })
+                    };
+                }
+                tokens.push();
+            }
+        });
+        let has_swap_devices = !self.swap_devices.is_empty();
+        let swap_devices = from_fn(move |tokens| {
+            tokens.append("[");
+            tokens.indent();
+            for device in self.swap_devices {
+                quote_in! { *tokens =>
+                    {
+                        device = $(quoted(device));
+                    }
+                }
+                tokens.push();
+            }
+            tokens.unindent();
+            tokens.append("]");
+        });
+        quote_in! { *tokens =>
+            {
+                $(if !self.available_kernel_modules.is_empty() {
+                    boot.initrd.availableKernelModules = $(strings(self.available_kernel_modules));
+                })
+                $(if !self.initrd_kernel_modules.is_empty() {
+                    boot.initrd.kernelModules = $(strings(self.initrd_kernel_modules));
+                })
+                $(if !self.kernel_modules.is_empty() {
+                    boot.kernelModules = $(strings(self.kernel_modules));
+                })
+                $file_systems
+                $(if has_swap_devices {
+                    swapDevices = $swap_devices;
+                })
+                $(if let Some(microcode) = self.microcode {
+                    hardware.cpu.$(microcode.vendor()).updateMicrocode = true;
+                })
+                $(if let Some(host_platform) = self.host_platform {
+                    nixpkgs.hostPlatform = $(quoted(host_platform));
+                })
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HardwareProfiles(pub Vec<HardwareProfile>);
+
+/// Plain data, referring to no input.
+impl Inputs for HardwareProfiles {
+    fn inputs(&self, _: &mut BTreeSet<String>) {}
+}
+
+impl Inherits for HardwareProfiles {
+    fn inherits(&self, _: &mut Inherited) {}
+}
+
+impl FormatInto<Nix> for HardwareProfiles {
+    fn format_into(self, tokens: &mut Tokens<Nix>) {
+        tokens.append("{");
+        tokens.indent();
+        for hardware_profile in self.0 {
+            quote_in!(*tokens => $(attr_name(&hardware_profile.name)) = $hardware_profile;);
+            tokens.push();
+        }
+        tokens.unindent();
+        tokens.append("}");
+    }
+}
@@ -0,0 +1,250 @@
+use crate::hive::attrs::attr_name;
+use crate::hive::flake::Inputs;
+use crate::hive::inherit::{Inherited, Inherits};
+use crate::hive::*;
+use genco::prelude::*;
+use std::collections::BTreeSet;
+
+pub struct Home {
+    pub username: String,
+    pub home_directory: String,
+    pub state_version: String,
+}
+
+pub struct HomeConfiguration {
+    pub imports: Vec<Import>,
+    /// `home.*` settings, only set for standalone home-manager users.
+    pub home: Option<Home>,
+    /// Name do **not** included in quote.
+    pub name: String,
+}
+
+impl HomeConfiguration {
+    /// An entry with only imports, such as the `cell.homeConfigurations.<name>`
+    /// a NixOS host imports.
+    pub fn new<T>(name: &str, imports: T) -> Self
+    where
+        T: IntoIterator<Item = Import>,
+    {
+        Self {
+            imports: imports.into_iter().collect(),
+            home: None,
+            name: String::from(name),
+        }
+    }
+
+    /// A standalone home-manager user. The home directory is below `/Users`
+    /// on darwin systems and below `/home` otherwise.
+    ///
+    /// ```
+    /// use genco::prelude::*;
+    /// use honey::hive::*;
+    ///
+    /// let alice = HomeConfiguration::user("alice", "home-23-05", "nixos-23-05", "x86_64-linux", "23.05")?;
+    ///
+    /// let toks = quote!($alice);
+    ///
+    /// assert_eq!(
+    ///     vec![
+    ///         "{",
+    ///         "    inputs,",
+    ///         "    ...",
+    ///         "}:",
+    ///         "",
+    ///         "let",
+    ///         "    inherit (inputs) home-23-05;",
+    ///         "    inherit (inputs) nixos-23-05;",
+    ///         "    bee-alice = {",
+    ///         "        bee = {",
+    ///         "            home = home-23-05;",
+    ///         "            pkgs = nixos-23-05.legacyPackages;",
+    ///         "            system = \"x86_64-linux\";",
+    ///         "        };",
+    ///         "    };",
+    ///         "in",
+    ///         "",
+    ///         "{",
+    ///         "    imports = [",
+    ///         "        bee-alice",
+    ///         "    ];",
+    ///         "    home = {",
+    ///         "        username = \"alice\";",
+    ///         "        homeDirectory = \"/home/alice\";",
+    ///         "        stateVersion = \"23.05\";",
+    ///         "    };",
+    ///         "}",
+    ///     ],
+    ///     toks.to_file_vec()?
+    /// );
+    /// # Ok::<_, Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn user(
+        username: &str,
+        home_manager: &str,
+        nixpkgs: &str,
+        system: &str,
+        state_version: &str,
+    ) -> Result<Self, bee::Error> {
+        let home_directory = if system.ends_with("-darwin") {
+            format!("/Users/{}", username)
+        } else {
+            format!("/home/{}", username)
+        };
+        Ok(Self {
+            imports: vec![Import::bee1(username, Some(home_manager), nixpkgs, system)?],
+            home: Some(Home {
+                username: String::from(username),
+                home_directory,
+                state_version: String::from(state_version),
+            }),
+            name: String::from(username),
+        })
+    }
+}
+
+impl Inputs for HomeConfiguration {
+    fn inputs(&self, names: &mut BTreeSet<String>) {
+        for import in &self.imports {
+            import.inputs(names);
+        }
+    }
+}
+
+impl Inherits for HomeConfiguration {
+    fn inherits(&self, inherited: &mut Inherited) {
+        for import in &self.imports {
+            import.inherits(inherited);
+        }
+    }
+}
+
+impl FormatInto<Nix> for HomeConfiguration {
+    fn format_into(self, tokens: &mut Tokens<Nix>) {
+        let imports = Imports(self.imports);
+        quote_in! { *tokens =>
+            {
+                imports = $(imports);
+                $(if let Some(home) = self.home {
+                    home = {
+                        username = $(quoted(home.username));
+                        homeDirectory = $(quoted(home.home_directory));
+                        stateVersion = $(quoted(home.state_version));
+                    };
+                })
+            }
+        }
+    }
+}
+
+pub struct HomeConfigurations(pub Vec<HomeConfiguration>);
+
+impl HomeConfigurations {
+    /// ```
+    /// use genco::prelude::*;
+    /// use honey::hive::*;
+    ///
+    /// let home_configurations = HomeConfigurations::new(["alice", "bob"], "home-23-05", "nixpkgs-darwin", "aarch64-darwin", "23.05")?;
+    ///
+    /// let toks = quote!($home_configurations);
+    ///
+    /// assert_eq!(
+    ///     vec![
+    ///         "{",
+    ///         "    inputs,",
+    ///         "    ...",
+    ///         "}:",
+    ///         "",
+    ///         "let",
+    ///         "    inherit (inputs) home-23-05;",
+    ///         "    inherit (inputs) nixpkgs-darwin;",
+    ///         "    bee-alice = {",
+    ///         "        bee = {",
+    ///         "            home = home-23-05;",
+    ///         "            pkgs = nixpkgs-darwin.legacyPackages;",
+    ///         "            system = \"aarch64-darwin\";",
+    ///         "        };",
+    ///         "    };",
+    ///         "    bee-bob = {",
+    ///         "        bee = {",
+    ///         "            home = home-23-05;",
+    ///         "            pkgs = nixpkgs-darwin.legacyPackages;",
+    ///         "            system = \"aarch64-darwin\";",
+    ///         "        };",
+    ///         "    };",
+    ///         "in",
+    ///         "",
+    ///         "{",
+    ///         "    alice = {",
+    ///         "        imports = [",
+    ///         "            bee-alice",
+    ///         "        ];",
+    ///         "        home = {",
+    ///         "            username = \"alice\";",
+    ///         "            homeDirectory = \"/Users/alice\";",
+    ///         "            stateVersion = \"23.05\";",
+    ///         "        };",
+    ///         "    };",
+    ///         "    bob = {",
+    ///         "        imports = [",
+    ///         "            bee-bob",
+    ///         "        ];",
+    ///         "        home = {",
+    ///         "            username = \"bob\";",
+    ///         "            homeDirectory = \"/Users/bob\";",
+    ///         "            stateVersion = \"23.05\";",
+    ///         "        };",
+    ///         "    };",
+    ///         "}",
+    ///     ],
+    ///     toks.to_file_vec()?
+    /// );
+    /// # Ok::<_, Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn new<'a, T>(
+        usernames: T,
+        home_manager: &str,
+        nixpkgs: &str,
+        system: &str,
+        state_version: &str,
+    ) -> Result<Self, bee::Error>
+    where
+        T: IntoIterator<Item = &'a str>,
+    {
+        let home_configurations = usernames
+            .into_iter()
+            .map(|username| {
+                HomeConfiguration::user(username, home_manager, nixpkgs, system, state_version)
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self(home_configurations))
+    }
+}
+
+impl Inputs for HomeConfigurations {
+    fn inputs(&self, names: &mut BTreeSet<String>) {
+        for home_configuration in &self.0 {
+            home_configuration.inputs(names);
+        }
+    }
+}
+
+impl Inherits for HomeConfigurations {
+    fn inherits(&self, inherited: &mut Inherited) {
+        for home_configuration in &self.0 {
+            home_configuration.inherits(inherited);
+        }
+    }
+}
+
+impl FormatInto<Nix> for HomeConfigurations {
+    fn format_into(self, tokens: &mut Tokens<Nix>) {
+        tokens.append("{");
+        tokens.indent();
+        for home_configuration in self.0 {
+            quote_in!(*tokens => $(attr_name(&home_configuration.name)) = $home_configuration;);
+            tokens.push();
+        }
+        tokens.unindent();
+        tokens.append("}");
+    }
+}
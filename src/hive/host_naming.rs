@@ -0,0 +1,201 @@
+use std::collections::BTreeSet;
+use std::fmt;
+
+/// Error returned when the names of a fleet do not identify its hosts.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Error {
+    /// A name appears twice.
+    Duplicate(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Duplicate(name) => write!(f, "duplicate host `{}`", name),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Numbered host names, `<prefix><n><suffix>` with `n` zero-padded to
+/// `width` digits. Numbers count from `start` by `step`, skipping the
+/// excluded ones, until there are `number` hosts.
+///
+/// ```
+/// use honey::hive::*;
+///
+/// assert_eq!(vec!["machine00", "machine01"], HostPattern::new("machine", 2).names());
+///
+/// let names = HostPattern::new("web", 4)
+///     .start(1)
+///     .width(3)
+///     .suffix(".dc1")
+///     .exclude([2])
+///     .names();
+///
+/// assert_eq!(vec!["web001.dc1", "web003.dc1", "web004.dc1", "web005.dc1"], names);
+///
+/// assert_eq!(
+///     vec!["node10", "node20", "node30"],
+///     HostPattern::new("node", 3).start(10).step(10).names()
+/// );
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HostPattern {
+    pub prefix: String,
+    pub suffix: String,
+    /// Number of hosts, excluded numbers not counted.
+    pub number: u32,
+    pub start: u32,
+    pub step: u32,
+    /// Minimum number of digits.
+    pub width: usize,
+    /// Numbers of retired hosts.
+    pub exclude: BTreeSet<u32>,
+}
+
+impl HostPattern {
+    /// `<prefix>00`, `<prefix>01`, ... as `NixosConfigurations::new1` names
+    /// them.
+    pub fn new(prefix: &str, number: u32) -> Self {
+        Self {
+            prefix: String::from(prefix),
+            suffix: String::new(),
+            number,
+            start: 0,
+            step: 1,
+            width: 2,
+            exclude: BTreeSet::new(),
+        }
+    }
+
+    pub fn start(mut self, start: u32) -> Self {
+        self.start = start;
+        self
+    }
+
+    /// Count by `step`, at least 1.
+    pub fn step(mut self, step: u32) -> Self {
+        self.step = step.max(1);
+        self
+    }
+
+    pub fn width(mut self, width: usize) -> Self {
+        self.width = width;
+        self
+    }
+
+    pub fn suffix(mut self, suffix: &str) -> Self {
+        self.suffix = String::from(suffix);
+        self
+    }
+
+    pub fn exclude<T>(mut self, numbers: T) -> Self
+    where
+        T: IntoIterator<Item = u32>,
+    {
+        self.exclude.extend(numbers);
+        self
+    }
+
+    /// The host names, in order. Stops early when the numbers run past
+    /// `u32::MAX`.
+    pub fn names(&self) -> Vec<String> {
+        let mut names = Vec::new();
+        let mut n = Some(self.start);
+        while let Some(i) = n {
+            if names.len() >= self.number as usize {
+                break;
+            }
+            if !self.exclude.contains(&i) {
+                names.push(format!(
+                    "{}{:0width$}{}",
+                    self.prefix,
+                    i,
+                    self.suffix,
+                    width = self.width
+                ));
+            }
+            n = i.checked_add(self.step.max(1));
+        }
+        names
+    }
+}
+
+/// How the hosts of a fleet are named, accepted by every fleet constructor.
+///
+/// ```
+/// use genco::prelude::*;
+/// use honey::hive::*;
+///
+/// let nixos_configurations = NixosConfigurations::new2(
+///     HostNaming::list(["db", "web.prod"]),
+///     None,
+///     "nixos-23-05",
+///     "x86_64-linux",
+/// )?;
+///
+/// let names: Vec<&str> = nixos_configurations
+///     .0
+///     .iter()
+///     .map(|configurations| configurations.name.as_str())
+///     .collect();
+///
+/// assert_eq!(vec!["db", "web.prod"], names);
+/// # Ok::<_, honey::hive::configurations::Error>(())
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum HostNaming {
+    Pattern(HostPattern),
+    /// Explicit names, in order.
+    List(Vec<String>),
+}
+
+impl HostNaming {
+    /// Hosts named explicitly.
+    pub fn list<T, S>(names: T) -> Self
+    where
+        T: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        HostNaming::List(names.into_iter().map(Into::into).collect())
+    }
+
+    /// The host names, in order. Fails on a name that appears twice:
+    ///
+    /// ```
+    /// use honey::hive::*;
+    /// use honey::hive::host_naming::Error;
+    ///
+    /// assert_eq!(
+    ///     Err(Error::Duplicate(String::from("web"))),
+    ///     HostNaming::list(["web", "db", "web"]).names()
+    /// );
+    /// ```
+    pub fn names(&self) -> Result<Vec<String>, Error> {
+        let names = match self {
+            HostNaming::Pattern(pattern) => pattern.names(),
+            HostNaming::List(names) => names.clone(),
+        };
+        let mut seen = BTreeSet::new();
+        for name in &names {
+            if !seen.insert(name) {
+                return Err(Error::Duplicate(name.clone()));
+            }
+        }
+        Ok(names)
+    }
+}
+
+impl From<HostPattern> for HostNaming {
+    fn from(pattern: HostPattern) -> Self {
+        HostNaming::Pattern(pattern)
+    }
+}
+
+impl From<Vec<String>> for HostNaming {
+    fn from(names: Vec<String>) -> Self {
+        HostNaming::list(names)
+    }
+}
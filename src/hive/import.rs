@@ -1,9 +1,17 @@
+use crate::hive::attrs::{binding_name, is_identifier};
+use crate::hive::flake::Inputs;
+use crate::hive::inherit::{Inherited, Inherits};
 use crate::hive::*;
+use crate::nix::NixValue;
 use genco::prelude::*;
+use std::collections::BTreeSet;
 
+#[derive(Clone)]
 pub struct Import {
+    /// The input the import pulls in, if any.
     pub inherit: Option<Inherit>,
-    pub name: nix::Tokens,
+    /// The imported value, e.g. `disko.nixosModules.disko`.
+    pub name: NixValue,
 }
 
 impl Import {
@@ -36,11 +44,8 @@ impl Import {
     /// ```
     pub fn new(path: &str, var: &str, name: &str) -> Self {
         Self {
-            inherit: Some(Inherit {
-                name: var.to_string(),
-                path: path.to_string(),
-            }),
-            name: quote!($name),
+            inherit: Some(Inherit::new(path, var)),
+            name: NixValue::select(NixValue::inherit(path, var), name),
         }
     }
 
@@ -68,12 +73,10 @@ impl Import {
     /// # Ok::<_, genco::fmt::Error>(())
     /// ```
     pub fn new1(name: &str) -> Self {
-        let s: Vec<&str> = name.split(".").collect();
-        let first = nix::argument(s[0]);
-        let remaining = &s[1..].join(".");
+        let s: Vec<&str> = name.split('.').collect();
         Self {
             inherit: None,
-            name: quote!($first$(if remaining != "" { .$remaining })),
+            name: NixValue::select1(NixValue::argument(s[0]), s[1..].iter().copied()),
         }
     }
 
@@ -103,10 +106,7 @@ impl Import {
     /// # Ok::<_, genco::fmt::Error>(())
     /// ```
     pub fn disko_module() -> Self {
-        Self {
-            inherit: Some(Inherit::disko()),
-            name: quote!(nixosModules.disko),
-        }
+        Self::new("inputs", "disko", "nixosModules.disko")
     }
 
     /// ```
@@ -131,11 +131,7 @@ impl Import {
     /// # Ok::<_, genco::fmt::Error>(())
     /// ```
     pub fn cell_hardware_profiles(name: &str) -> Self {
-        let cell = nix::argument("cell");
-        Self {
-            inherit: None,
-            name: quote!($cell.hardwareProfiles.$name),
-        }
+        Self::cell("hardwareProfiles", name)
     }
 
     /// ```
@@ -160,11 +156,7 @@ impl Import {
     /// # Ok::<_, genco::fmt::Error>(())
     /// ```
     pub fn cell_nixos_profiles(name: &str) -> Self {
-        let cell = nix::argument("cell");
-        Self {
-            inherit: None,
-            name: quote!($cell.nixosProfiles.$name),
-        }
+        Self::cell("nixosProfiles", name)
     }
 
     /// ```
@@ -189,29 +181,167 @@ impl Import {
     /// # Ok::<_, genco::fmt::Error>(())
     /// ```
     pub fn cell_disko_configurations(name: &str) -> Self {
-        let cell = nix::argument("cell");
-        Self {
-            inherit: None,
-            name: quote!($cell.diskoConfigurations.$name),
-        }
+        Self::cell("diskoConfigurations", name)
     }
 
     pub fn cell_home_configurations(name: &str) -> Self {
-        let cell = nix::argument("cell");
-        Self {
-            inherit: None,
-            name: quote!($cell.homeConfigurations.$name),
-        }
+        Self::cell("homeConfigurations", name)
+    }
+
+    pub fn cell_nixos_configurations(name: &str) -> Self {
+        Self::cell("nixosConfigurations", name)
     }
 
     pub fn cell_nixos_modules(name: &str) -> Self {
-        let cell = nix::argument("cell");
-        Self {
-            inherit: None,
-            name: quote!($cell.nixosModules.$name),
+        Self::cell("nixosModules", name)
+    }
+
+    /// ```
+    /// use genco::prelude::*;
+    /// use honey::hive::*;
+    ///
+    /// let my_darwin_modules = Import::cell_darwin_modules("my-darwin-modules");
+    ///
+    /// let toks = quote!($my_darwin_modules);
+    ///
+    /// assert_eq!(
+    ///     vec![
+    ///         "{",
+    ///         "    cell,",
+    ///         "    ...",
+    ///         "}:",
+    ///         "",
+    ///         "cell.darwinModules.my-darwin-modules",
+    ///     ],
+    ///     toks.to_file_vec()?
+    /// );
+    /// # Ok::<_, genco::fmt::Error>(())
+    /// ```
+    pub fn cell_darwin_modules(name: &str) -> Self {
+        Self::cell("darwinModules", name)
+    }
+
+    pub fn cell_darwin_profiles(name: &str) -> Self {
+        Self::cell("darwinProfiles", name)
+    }
+
+    /// `<block>.<target>` of another cell of the hive, e.g.
+    /// `inputs.cells.common.nixosProfiles.base`. The cell is inherited from
+    /// `inputs.cells` unless its name would not make a binding, or would
+    /// shadow the `cell` and `inputs` arguments. Configurations that also
+    /// inherit an input of the same name select the cell in full, see
+    /// `inherit::unshadow`.
+    ///
+    /// ```
+    /// use genco::prelude::*;
+    /// use honey::hive::*;
+    ///
+    /// let configurations = Configurations::new("machine00", [
+    ///     Import::cells_nixos_profiles("common", "base"),
+    ///     Import::cells("common", "nixosModules", "ssh"),
+    ///     Import::cells("cell", "nixosProfiles", "server"),
+    ///     Import::cells("web.prod", "nixosProfiles", "web"),
+    /// ]);
+    ///
+    /// let toks = quote!($configurations);
+    ///
+    /// assert_eq!(
+    ///     vec![
+    ///         "{",
+    ///         "    inputs,",
+    ///         "    ...",
+    ///         "}:",
+    ///         "",
+    ///         "let",
+    ///         "    inherit (inputs.cells) common;",
+    ///         "in",
+    ///         "",
+    ///         "{",
+    ///         "    imports = [",
+    ///         "        common.nixosProfiles.base",
+    ///         "        common.nixosModules.ssh",
+    ///         "        inputs.cells.cell.nixosProfiles.server",
+    ///         "        inputs.cells.\"web.prod\".nixosProfiles.web",
+    ///         "    ];",
+    ///         "}",
+    ///     ],
+    ///     toks.to_file_vec()?
+    /// );
+    ///
+    /// let configurations = Configurations::new("machine00", [
+    ///     Import::cells_nixos_profiles("disko", "base"),
+    ///     Import::disko_module(),
+    /// ]);
+    ///
+    /// let toks = quote!($configurations);
+    ///
+    /// assert_eq!(
+    ///     vec![
+    ///         "{",
+    ///         "    inputs,",
+    ///         "    ...",
+    ///         "}:",
+    ///         "",
+    ///         "let",
+    ///         "    inherit (inputs) disko;",
+    ///         "in",
+    ///         "",
+    ///         "{",
+    ///         "    imports = [",
+    ///         "        inputs.cells.disko.nixosProfiles.base",
+    ///         "        disko.nixosModules.disko",
+    ///         "    ];",
+    ///         "}",
+    ///     ],
+    ///     toks.to_file_vec()?
+    /// );
+    /// # Ok::<_, genco::fmt::Error>(())
+    /// ```
+    pub fn cells(cell: &str, block: &str, target: &str) -> Self {
+        if is_identifier(cell) && !matches!(cell, "cell" | "inputs") {
+            Self {
+                inherit: Some(Inherit::new("inputs.cells", cell)),
+                name: NixValue::select1(NixValue::inherit("inputs.cells", cell), [block, target]),
+            }
+        } else {
+            Self {
+                inherit: None,
+                name: NixValue::select1(
+                    NixValue::argument("inputs"),
+                    ["cells", cell, block, target],
+                ),
+            }
         }
     }
 
+    pub fn cells_disko_configurations(cell: &str, name: &str) -> Self {
+        Self::cells(cell, "diskoConfigurations", name)
+    }
+
+    pub fn cells_hardware_profiles(cell: &str, name: &str) -> Self {
+        Self::cells(cell, "hardwareProfiles", name)
+    }
+
+    pub fn cells_home_configurations(cell: &str, name: &str) -> Self {
+        Self::cells(cell, "homeConfigurations", name)
+    }
+
+    pub fn cells_nixos_modules(cell: &str, name: &str) -> Self {
+        Self::cells(cell, "nixosModules", name)
+    }
+
+    pub fn cells_nixos_profiles(cell: &str, name: &str) -> Self {
+        Self::cells(cell, "nixosProfiles", name)
+    }
+
+    pub fn cells_darwin_modules(cell: &str, name: &str) -> Self {
+        Self::cells(cell, "darwinModules", name)
+    }
+
+    pub fn cells_darwin_profiles(cell: &str, name: &str) -> Self {
+        Self::cells(cell, "darwinProfiles", name)
+    }
+
     /// ```
     /// use genco::prelude::*;
     /// use honey::hive::*;
@@ -246,18 +376,11 @@ impl Import {
     /// # Ok::<_, genco::fmt::Error>(())
     /// ```
     pub fn bee(name: &str) -> Self {
-        let cell = nix::argument("cell");
-        let home_manager = Some(Inherit::home_manager());
-        let nixpkgs = quote!($cell.pkgs.$name);
-        let bee = Variable::bee(
-            &format!("bee-{}", name),
-            home_manager,
-            nixpkgs,
-            "x86_64-linux",
-        );
+        let pkgs = NixValue::select1(NixValue::argument("cell"), ["pkgs", name]);
+        let bee = Bee::new("x86_64-linux", pkgs).home(Inherit::home_manager());
         Self {
             inherit: None,
-            name: quote!($bee),
+            name: bee.variable(&binding_name("bee", name)).into(),
         }
     }
 
@@ -265,7 +388,7 @@ impl Import {
     /// use genco::prelude::*;
     /// use honey::hive::*;
     ///
-    /// let bee = Import::bee1("machine1", Some("home-23-05"), "nixos-23-05", "x86_64-linux");
+    /// let bee = Import::bee1("machine1", Some("home-23-05"), "nixos-23-05", "x86_64-linux")?;
     ///
     /// let toks = quote!($bee);
     ///
@@ -292,21 +415,18 @@ impl Import {
     ///     ],
     ///     toks.to_file_vec()?
     /// );
-    /// # Ok::<_, genco::fmt::Error>(())
+    /// # Ok::<_, Box<dyn std::error::Error>>(())
     /// ```
-    pub fn bee1(name: &str, home_manager: Option<&str>, nixpkgs: &str, system: &str) -> Self {
-        let home_manager = if let Some(home_manager) = home_manager {
-            Some(Inherit::new("inputs", home_manager))
-        } else {
-            None
-        };
-        let nixpkgs = Inherit::new("inputs", nixpkgs);
-        let pkgs = quote!($nixpkgs.legacyPackages);
-        let bee = Variable::bee(&format!("bee-{}", name), home_manager, pkgs, system);
-        Self {
-            inherit: None,
-            name: quote!($bee),
-        }
+    pub fn bee1(
+        name: &str,
+        home_manager: Option<&str>,
+        nixpkgs: &str,
+        system: &str,
+    ) -> Result<Self, bee::Error> {
+        let nixpkgs = NixValue::select(NixValue::inherit("inputs", nixpkgs), "legacyPackages");
+        let mut bee = Bee::new(system, nixpkgs);
+        bee.home = home_manager.map(|home_manager| Inherit::new("inputs", home_manager).into());
+        Self::from_bee(&binding_name("bee", name), bee)
     }
 
     /// ```
@@ -316,7 +436,7 @@ impl Import {
     /// let home_manager = Some(Inherit::home_manager());
     /// let nixpkgs = Inherit::nixpkgs();
     ///
-    /// let bee = Import::bee2(home_manager, nixpkgs, "x86_64-linux");
+    /// let bee = Import::bee2(home_manager, nixpkgs, "x86_64-linux")?;
     ///
     /// let toks = quote!($bee);
     ///
@@ -343,28 +463,153 @@ impl Import {
     ///     ],
     ///     toks.to_file_vec()?
     /// );
-    /// # Ok::<_, genco::fmt::Error>(())
+    /// # Ok::<_, Box<dyn std::error::Error>>(())
     /// ```
-    pub fn bee2<M, N>(home_manager: Option<M>, nixpkgs: N, system: &str) -> Self
+    pub fn bee2<M, N>(home_manager: Option<M>, nixpkgs: N, system: &str) -> Result<Self, bee::Error>
     where
-        M: Into<nix::Tokens>,
-        N: Into<nix::Tokens>,
+        M: Into<NixValue>,
+        N: Into<NixValue>,
     {
-        let bee = Variable::bee("bee", home_manager, nixpkgs, system);
+        let mut bee = Bee::new(system, nixpkgs);
+        bee.home = home_manager.map(Into::into);
+        Self::from_bee("bee", bee)
+    }
+
+    /// Bee for a nix-darwin host.
+    ///
+    /// ```
+    /// use genco::prelude::*;
+    /// use honey::hive::*;
+    ///
+    /// let bee = Import::bee3("macbook1", "darwin", Some("home-23-05"), "nixpkgs-darwin", "aarch64-darwin")?;
+    ///
+    /// let toks = quote!($bee);
+    ///
+    /// assert_eq!(
+    ///     vec![
+    ///         "{",
+    ///         "    inputs,",
+    ///         "    ...",
+    ///         "}:",
+    ///         "",
+    ///         "let",
+    ///         "    inherit (inputs) darwin;",
+    ///         "    inherit (inputs) home-23-05;",
+    ///         "    inherit (inputs) nixpkgs-darwin;",
+    ///         "    bee-macbook1 = {",
+    ///         "        bee = {",
+    ///         "            darwin = darwin;",
+    ///         "            home = home-23-05;",
+    ///         "            pkgs = nixpkgs-darwin.legacyPackages;",
+    ///         "            system = \"aarch64-darwin\";",
+    ///         "        };",
+    ///         "    };",
+    ///         "in",
+    ///         "",
+    ///         "bee-macbook1"
+    ///     ],
+    ///     toks.to_file_vec()?
+    /// );
+    /// # Ok::<_, Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn bee3(
+        name: &str,
+        darwin: &str,
+        home_manager: Option<&str>,
+        nixpkgs: &str,
+        system: &str,
+    ) -> Result<Self, bee::Error> {
+        let nixpkgs = NixValue::select(NixValue::inherit("inputs", nixpkgs), "legacyPackages");
+        let mut bee = Bee::new(system, nixpkgs).darwin(Inherit::new("inputs", darwin));
+        bee.home = home_manager.map(|home_manager| Inherit::new("inputs", home_manager).into());
+        Self::from_bee(&binding_name("bee", name), bee)
+    }
+
+    /// Import a bee bound to `name` in the `let` block.
+    ///
+    /// ```
+    /// use genco::prelude::*;
+    /// use honey::hive::*;
+    ///
+    /// let bee = Bee::new("x86_64-linux", Inherit::nixpkgs()).colmena(Inherit::new("inputs", "colmena"));
+    ///
+    /// let toks = quote!($(Import::from_bee("bee-machine1", bee)?));
+    ///
+    /// assert_eq!(
+    ///     vec![
+    ///         "{",
+    ///         "    inputs,",
+    ///         "    ...",
+    ///         "}:",
+    ///         "",
+    ///         "let",
+    ///         "    inherit (inputs) colmena;",
+    ///         "    inherit (inputs) nixpkgs;",
+    ///         "    bee-machine1 = {",
+    ///         "        bee = {",
+    ///         "            colmena = colmena;",
+    ///         "            pkgs = nixpkgs;",
+    ///         "            system = \"x86_64-linux\";",
+    ///         "        };",
+    ///         "    };",
+    ///         "in",
+    ///         "",
+    ///         "bee-machine1"
+    ///     ],
+    ///     toks.to_file_vec()?
+    /// );
+    /// # Ok::<_, Box<dyn std::error::Error>>(())
+    /// ```
+    ///
+    /// Fails if the bee does not validate, see `Bee::validate`.
+    ///
+    /// ```
+    /// use honey::hive::*;
+    /// use honey::hive::bee::Error;
+    ///
+    /// let bee = Bee::new("x86_64-linux", Inherit::nixpkgs()).darwin(Inherit::new("inputs", "darwin"));
+    ///
+    /// assert_eq!(
+    ///     Some(Error::System("darwin", String::from("x86_64-linux"))),
+    ///     Import::from_bee("bee-machine1", bee).err()
+    /// );
+    ///
+    /// assert_eq!(
+    ///     Some(Error::InvalidSystem(String::from("foo"))),
+    ///     Import::bee1("machine1", None, "nixos-23-05", "foo").err()
+    /// );
+    /// ```
+    pub fn from_bee(name: &str, bee: Bee) -> Result<Self, bee::Error> {
+        Ok(Self {
+            inherit: None,
+            name: bee.build(name)?.into(),
+        })
+    }
+
+    /// `cell.<block>.<name>`
+    fn cell(block: &str, name: &str) -> Self {
         Self {
             inherit: None,
-            name: quote!($bee),
+            name: NixValue::select1(NixValue::argument("cell"), [block, name]),
         }
     }
 }
 
+impl Inputs for Import {
+    fn inputs(&self, names: &mut BTreeSet<String>) {
+        self.name.inputs(names);
+    }
+}
+
+impl Inherits for Import {
+    fn inherits(&self, inherited: &mut Inherited) {
+        self.name.inherits(inherited);
+    }
+}
+
 impl FormatInto<Nix> for Import {
     fn format_into(self, tokens: &mut Tokens<Nix>) {
-        if let Some(inherit) = self.inherit {
-            quote_in!(*tokens => $inherit.$(self.name))
-        } else {
-            quote_in!(*tokens => $(self.name))
-        }
+        tokens.append(self.name);
     }
 }
 
@@ -379,6 +624,22 @@ impl IntoIterator for Imports {
     }
 }
 
+impl Inputs for Imports {
+    fn inputs(&self, names: &mut BTreeSet<String>) {
+        for import in &self.0 {
+            import.inputs(names);
+        }
+    }
+}
+
+impl Inherits for Imports {
+    fn inherits(&self, inherited: &mut Inherited) {
+        for import in &self.0 {
+            import.inherits(inherited);
+        }
+    }
+}
+
 impl FormatInto<Nix> for Imports {
     /// ```
     /// use genco::prelude::*;
@@ -1,5 +1,11 @@
+use crate::hive::flake::Inputs;
+use crate::nix::value::StringPart;
+use crate::nix::NixValue;
 use genco::prelude::*;
+use genco::tokens::{Item, ItemStr};
+use std::collections::{BTreeMap, BTreeSet};
 
+#[derive(Clone)]
 pub struct Inherit {
     pub name: String,
     pub path: String,
@@ -143,9 +149,310 @@ impl From<Inherit> for nix::Tokens {
     }
 }
 
+impl From<Inherit> for NixValue {
+    fn from(inherit: Inherit) -> Self {
+        NixValue::Inherit {
+            path: inherit.path,
+            name: inherit.name,
+        }
+    }
+}
+
+impl Inputs for Inherit {
+    fn inputs(&self, names: &mut BTreeSet<String>) {
+        NixValue::from(self.clone()).inputs(names);
+    }
+}
+
 impl FormatInto<Nix> for Inherit {
     fn format_into(self, tokens: &mut Tokens<Nix>) {
         let inherit = nix::inherit(self.path, self.name);
         tokens.append(inherit);
     }
 }
+
+/// The `(path, name)` of the inherits a value writes, see `Inherits`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Inherited {
+    pub inherits: BTreeSet<(String, String)>,
+    /// The inherits a `let` variable refers to. The variable writes their
+    /// names as they are, so they keep their binding.
+    pub pinned: BTreeSet<(String, String)>,
+}
+
+impl Inherited {
+    /// The inherits of `value`.
+    ///
+    /// ```
+    /// use honey::hive::*;
+    /// use honey::hive::inherit::Inherited;
+    ///
+    /// let inherited = Inherited::of(&Imports(vec![Import::bee1("machine00", None, "nixos-23-05", "x86_64-linux")?, Import::disko_module()]));
+    ///
+    /// let pair = |name: &str| (String::from("inputs"), String::from(name));
+    /// assert_eq!(vec![pair("disko"), pair("nixos-23-05")], inherited.inherits.into_iter().collect::<Vec<_>>());
+    /// assert_eq!(vec![pair("nixos-23-05")], inherited.pinned.into_iter().collect::<Vec<_>>());
+    /// # Ok::<_, honey::hive::bee::Error>(())
+    /// ```
+    pub fn of<T>(value: &T) -> Self
+    where
+        T: Inherits + ?Sized,
+    {
+        let mut inherited = Self::default();
+        value.inherits(&mut inherited);
+        inherited
+    }
+}
+
+/// Values that know the inherits they write, so that `merge` and `unshadow`
+/// can find them among the tokens. Inherits inside `NixValue::Tokens` are
+/// not known.
+pub trait Inherits {
+    fn inherits(&self, inherited: &mut Inherited);
+}
+
+impl Inherits for NixValue {
+    fn inherits(&self, inherited: &mut Inherited) {
+        match self {
+            NixValue::Inherit { path, name } => {
+                inherited.inherits.insert((path.clone(), name.clone()));
+            }
+            NixValue::Variable { value, .. } => {
+                let variable = Inherited::of(&**value);
+                inherited.pinned.extend(variable.inherits.iter().cloned());
+                inherited.inherits.extend(variable.inherits);
+            }
+            NixValue::String(parts) => {
+                for part in parts {
+                    if let StringPart::Interpolation(value) = part {
+                        value.inherits(inherited);
+                    }
+                }
+            }
+            NixValue::List(values) => {
+                for value in values {
+                    value.inherits(inherited);
+                }
+            }
+            NixValue::AttrSet(attrs) | NixValue::RecAttrSet(attrs) => {
+                for (_, value) in attrs {
+                    value.inherits(inherited);
+                }
+            }
+            NixValue::Lambda { body: value, .. } | NixValue::Select { value, .. } => {
+                value.inherits(inherited)
+            }
+            NixValue::Let { bindings, body } => {
+                for (_, value) in bindings {
+                    value.inherits(inherited);
+                }
+                body.inherits(inherited);
+            }
+            NixValue::With {
+                namespace: first,
+                body: second,
+            }
+            | NixValue::Apply {
+                function: first,
+                argument: second,
+            } => {
+                first.inherits(inherited);
+                second.inherits(inherited);
+            }
+            NixValue::Null
+            | NixValue::Bool(_)
+            | NixValue::Int(_)
+            | NixValue::Float(_)
+            | NixValue::Path(_)
+            | NixValue::Ident(_)
+            | NixValue::Argument(_)
+            | NixValue::Tokens(_) => (),
+        }
+    }
+}
+
+impl Inherits for Inherit {
+    fn inherits(&self, inherited: &mut Inherited) {
+        NixValue::from(self.clone()).inherits(inherited);
+    }
+}
+
+/// The genco import each inherit is written with, to find it among tokens.
+fn imports<'a, I>(inherits: I) -> BTreeMap<nix::Import, &'a (String, String)>
+where
+    I: IntoIterator<Item = &'a (String, String)>,
+{
+    inherits
+        .into_iter()
+        .map(|inherit| (nix::inherit(inherit.0.clone(), inherit.1.clone()), inherit))
+        .collect()
+}
+
+/// Tokens with their inherits taken out, see `hoist`.
+pub(crate) struct Hoisted {
+    pub tokens: nix::Tokens,
+    /// `(path, name)` of every inherit taken out.
+    pub inherits: BTreeSet<(String, String)>,
+}
+
+/// Take `inherits` out of `tokens`, writing the inherited names in their
+/// place.
+pub(crate) fn hoist(tokens: &nix::Tokens, inherits: &BTreeSet<(String, String)>) -> Hoisted {
+    let imports = imports(inherits);
+    let mut hoisted = BTreeSet::new();
+    let mut items = Vec::new();
+    for item in tokens {
+        match item {
+            Item::Lang(_, import) | Item::Register(_, import) => match imports.get(&**import) {
+                Some(inherit) => {
+                    if let Item::Lang(..) = item {
+                        items.push(Item::Literal(ItemStr::from(inherit.1.clone())));
+                    }
+                    hoisted.insert((*inherit).clone());
+                }
+                None => items.push(item.clone()),
+            },
+            item => items.push(item.clone()),
+        }
+    }
+    Hoisted {
+        tokens: items.into_iter().collect(),
+        inherits: hoisted,
+    }
+}
+
+/// Merge the inherits of `tokens` from the same source into one
+/// `inherit (source) a b c;` line, leaving out duplicates. `inherited` names
+/// the inherits to look for, as collected from the value the tokens were
+/// rendered from.
+///
+/// ```
+/// use genco::prelude::*;
+/// use honey::hive::*;
+/// use honey::hive::inherit;
+///
+/// let configurations = NixosConfigurations(vec![
+///     Configurations::new("machine00", vec![Import::bee1("machine00", Some("home-23-05"), "nixos-23-05", "x86_64-linux")?, Import::disko_module()]),
+///     Configurations::new("machine01", vec![Import::bee1("machine01", None, "nixos-23-05", "x86_64-linux")?, Import::disko_module()]),
+/// ]);
+///
+/// let inherited = inherit::Inherited::of(&configurations);
+/// let toks = inherit::merge(&quote!($configurations), &inherited);
+///
+/// assert_eq!(
+///     vec![
+///         "{",
+///         "    inputs,",
+///         "    ...",
+///         "}:",
+///         "",
+///         "let",
+///         "    inherit (inputs) disko home-23-05 nixos-23-05;",
+///         "    bee-machine00 = {",
+///     ],
+///     toks.to_file_vec()?[..8]
+/// );
+/// # Ok::<_, Box<dyn std::error::Error>>(())
+/// ```
+pub fn merge(tokens: &nix::Tokens, inherited: &Inherited) -> nix::Tokens {
+    let Hoisted {
+        tokens: mut merged,
+        inherits,
+    } = hoist(tokens, &inherited.inherits);
+    let mut sources: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for (path, name) in inherits {
+        sources.entry(path).or_default().push(name);
+    }
+    for (path, names) in sources {
+        merged.register(nix::inherit(path, names.join(" ")));
+    }
+    merged
+}
+
+/// Write inherits whose name is also inherited from another path in full,
+/// e.g. `inputs.cells.disko` for a cell named like the `disko` input, so
+/// that the `let` block binds every name once. The inherit from the
+/// shortest path keeps the binding.
+///
+/// ```
+/// use genco::prelude::*;
+/// use honey::hive::*;
+/// use honey::hive::inherit;
+///
+/// let imports = Imports(vec![
+///     Import::cells_nixos_profiles("disko", "base"),
+///     Import::disko_module(),
+/// ]);
+///
+/// let inherited = inherit::Inherited::of(&imports);
+/// let toks = inherit::unshadow(&quote!($imports), &inherited);
+///
+/// assert_eq!(
+///     vec![
+///         "{",
+///         "    inputs,",
+///         "    ...",
+///         "}:",
+///         "",
+///         "let",
+///         "    inherit (inputs) disko;",
+///         "in",
+///         "",
+///         "[",
+///         "    inputs.cells.disko.nixosProfiles.base",
+///         "    disko.nixosModules.disko",
+///         "]",
+///     ],
+///     toks.to_file_vec()?
+/// );
+/// # Ok::<_, genco::fmt::Error>(())
+/// ```
+pub fn unshadow(tokens: &nix::Tokens, inherited: &Inherited) -> nix::Tokens {
+    let Hoisted { inherits, .. } = hoist(tokens, &inherited.inherits);
+    let mut paths: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for (path, name) in &inherits {
+        paths.entry(name).or_default().push(path);
+    }
+    let mut shadowed = BTreeSet::new();
+    for (name, mut paths) in paths {
+        paths.sort_by_key(|path| {
+            (
+                !inherited
+                    .pinned
+                    .contains(&(path.to_string(), name.to_string())),
+                path.split('.').count(),
+            )
+        });
+        shadowed.extend(
+            paths
+                .into_iter()
+                .skip(1)
+                .map(|path| (path.to_string(), name.to_string())),
+        );
+    }
+    if shadowed.is_empty() {
+        return tokens.clone();
+    }
+    let imports = imports(&shadowed);
+    let mut items = Vec::new();
+    let mut arguments = BTreeSet::new();
+    for item in tokens {
+        match item {
+            Item::Lang(_, import) | Item::Register(_, import) => match imports.get(&**import) {
+                Some((path, name)) => {
+                    if let Item::Lang(..) = item {
+                        items.push(Item::Literal(ItemStr::from(format!("{}.{}", path, name))));
+                    }
+                    arguments.extend(path.split('.').next().map(String::from));
+                }
+                None => items.push(item.clone()),
+            },
+            item => items.push(item.clone()),
+        }
+    }
+    let mut unshadowed: nix::Tokens = items.into_iter().collect();
+    for argument in arguments {
+        unshadowed.register(nix::argument(argument));
+    }
+    unshadowed
+}
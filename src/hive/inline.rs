@@ -0,0 +1,129 @@
+use crate::hive::flake::Inputs;
+use crate::hive::inherit::{Inherited, Inherits};
+use crate::nix::NixValue;
+use genco::prelude::*;
+use std::collections::BTreeSet;
+
+/// A module written inline in `imports = [ ... ]`, for per-host settings
+/// too small for a cell block of their own.
+///
+/// ```
+/// use genco::prelude::*;
+/// use honey::hive::*;
+///
+/// let configurations = Configurations::builder("machine00")
+///     .bee(Import::bee1("machine00", None, "nixos-23-05", "x86_64-linux")?)
+///     .disko(false)
+///     .inline(
+///         Inline::new()
+///             .set("networking.hostName", "machine00")
+///             .set("system.stateVersion", "23.05")
+///             .set("services.openssh.enable", true),
+///     )
+///     .build();
+///
+/// let toks = quote!($configurations);
+///
+/// assert_eq!(
+///     vec![
+///         "{",
+///         "    cell,",
+///         "    inputs,",
+///         "    ...",
+///         "}:",
+///         "",
+///         "let",
+///         "    inherit (inputs) nixos-23-05;",
+///         "    bee-machine00 = {",
+///         "        bee = {",
+///         "            pkgs = nixos-23-05.legacyPackages;",
+///         "            system = \"x86_64-linux\";",
+///         "        };",
+///         "    };",
+///         "in",
+///         "",
+///         "{",
+///         "    imports = [",
+///         "        bee-machine00",
+///         "        cell.hardwareProfiles.machine00",
+///         "        cell.homeConfigurations.machine00",
+///         "        cell.nixosModules.machine00",
+///         "        cell.nixosProfiles.machine00",
+///         "        {",
+///         "            networking.hostName = \"machine00\";",
+///         "            system.stateVersion = \"23.05\";",
+///         "            services.openssh.enable = true;",
+///         "        }",
+///         "    ];",
+///         "}",
+///     ],
+///     toks.to_file_vec()?
+/// );
+///
+/// let parsed = Configurations::parse("machine00", &toks.to_file_string()?)?;
+///
+/// assert!(matches!(
+///     &parsed.configurations[5],
+///     ConfigurationType::Inline(inline) if inline.attrs[1].1 == "23.05".into()
+/// ));
+/// # Ok::<_, Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Inline {
+    /// Attribute paths with their values, in order.
+    pub attrs: Vec<(Vec<String>, NixValue)>,
+}
+
+impl Inline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the dotted attribute `path`, e.g. `networking.hostName`.
+    pub fn set<T>(self, path: &str, value: T) -> Self
+    where
+        T: Into<NixValue>,
+    {
+        self.set1(path.split('.'), value)
+    }
+
+    /// Set an attribute path given as separate names, for names holding a
+    /// dot themselves.
+    pub fn set1<'a, P, T>(mut self, path: P, value: T) -> Self
+    where
+        P: IntoIterator<Item = &'a str>,
+        T: Into<NixValue>,
+    {
+        let path = path.into_iter().map(String::from).collect();
+        self.attrs.push((path, value.into()));
+        self
+    }
+}
+
+impl From<Inline> for NixValue {
+    fn from(inline: Inline) -> Self {
+        NixValue::AttrSet(inline.attrs)
+    }
+}
+
+impl Inputs for Inline {
+    fn inputs(&self, names: &mut BTreeSet<String>) {
+        for (_, value) in &self.attrs {
+            value.inputs(names);
+        }
+    }
+}
+
+impl Inherits for Inline {
+    fn inherits(&self, inherited: &mut Inherited) {
+        for (_, value) in &self.attrs {
+            value.inherits(inherited);
+        }
+    }
+}
+
+impl FormatInto<Nix> for Inline {
+    fn format_into(self, tokens: &mut Tokens<Nix>) {
+        tokens.append(NixValue::from(self));
+    }
+}
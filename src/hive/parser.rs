@@ -0,0 +1,635 @@
+use crate::hive::*;
+use crate::nix::value::{self, StringPart};
+use crate::nix::{AttrName, Binding, Expr, NixValue, Param, StrPart, UnaryOp};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+
+/// Error returned when a cell block can not be read back into the model.
+#[derive(Debug)]
+pub enum Error {
+    /// The input is not valid Nix.
+    Syntax(crate::nix::Error),
+    /// The input is valid Nix, but not something honey can represent.
+    Unsupported(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Syntax(error) => write!(f, "syntax error at {}", error),
+            Error::Unsupported(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Syntax(error) => Some(error),
+            Error::Unsupported(_) => None,
+        }
+    }
+}
+
+impl From<crate::nix::Error> for Error {
+    fn from(error: crate::nix::Error) -> Self {
+        Error::Syntax(error)
+    }
+}
+
+/// Attribute paths of a flattened attribute set with their values.
+type Attrs<'a> = Vec<(Vec<String>, &'a Expr)>;
+
+fn unsupported<T>(message: String) -> Result<T, Error> {
+    Err(Error::Unsupported(message))
+}
+
+/// Names every Nix file can refer to without binding them.
+const BUILTINS: [&str; 14] = [
+    "abort",
+    "baseNameOf",
+    "builtins",
+    "derivation",
+    "dirOf",
+    "false",
+    "fetchTarball",
+    "import",
+    "isNull",
+    "map",
+    "null",
+    "removeAttrs",
+    "throw",
+    "true",
+];
+
+/// Names in scope of the block body: the function arguments, the inherits,
+/// the bee bindings and any other bindings of the `let` block.
+#[derive(Default)]
+struct Scope {
+    arguments: BTreeSet<String>,
+    inherits: HashMap<String, String>,
+    bees: HashMap<String, Bee>,
+    variables: HashMap<String, Expr>,
+}
+
+/// Names bound inside an expression rather than by the file.
+#[derive(Clone, Default)]
+struct Bound {
+    names: Vec<String>,
+    /// The `let` binding whose value this is. genco only hoists inherits and
+    /// arguments out of a binding, so it can not refer to other bindings.
+    variable: Option<String>,
+    /// Inside `with`, where any name may come from the namespace.
+    with: bool,
+}
+
+impl Bound {
+    fn with_names<'a, T>(&self, names: T) -> Self
+    where
+        T: IntoIterator<Item = &'a str>,
+    {
+        let mut bound = self.clone();
+        bound.names.extend(names.into_iter().map(String::from));
+        bound
+    }
+
+    fn contains(&self, name: &str) -> bool {
+        self.names.iter().any(|other| other == name)
+    }
+}
+
+/// Split the file into its scope and body.
+fn scope(expr: Expr) -> Result<(Scope, Expr), Error> {
+    let mut scope = Scope::default();
+    let body = match expr {
+        Expr::Lambda {
+            param: Param::Pattern { formals, .. },
+            body,
+        } => {
+            for formal in formals {
+                scope.arguments.insert(formal.name);
+            }
+            *body
+        }
+        Expr::Lambda { param, .. } => {
+            return unsupported(format!(
+                "expected a `{{ cell, inputs, ... }}:` argument pattern, found `{}`",
+                param
+            ))
+        }
+        expr => {
+            // Scoped files leave out the argument pattern.
+            scope.arguments.insert(String::from("cell"));
+            scope.arguments.insert(String::from("inputs"));
+            expr
+        }
+    };
+    let body = match body {
+        Expr::Let { bindings, body } => {
+            // Inherits first, since bee bindings may refer to any of them.
+            let (inherits, bindings): (Vec<_>, Vec<_>) = bindings
+                .into_iter()
+                .partition(|binding| matches!(binding, Binding::Inherit { .. }));
+            for binding in inherits.into_iter().chain(bindings) {
+                scope.binding(binding)?;
+            }
+            *body
+        }
+        body => body,
+    };
+    Ok((scope, body))
+}
+
+fn attr_name(name: &AttrName) -> Result<&str, Error> {
+    match name.as_str() {
+        Some(name) => Ok(name),
+        None => unsupported(format!(
+            "unsupported interpolated attribute name `{}`",
+            name
+        )),
+    }
+}
+
+/// Flatten nested attribute sets into `(path, value)` pairs, so that
+/// `bee.system = x;` and `bee = { system = x; };` read the same.
+fn flatten<'a>(
+    bindings: &'a [Binding],
+    prefix: &[String],
+    out: &mut Attrs<'a>,
+) -> Result<(), Error> {
+    for binding in bindings {
+        match binding {
+            Binding::Attr { path, value } => {
+                let mut full = prefix.to_vec();
+                for name in path {
+                    full.push(attr_name(name)?.to_string());
+                }
+                match value {
+                    Expr::AttrSet {
+                        recursive: false,
+                        bindings,
+                    } if full.len() < 2 => flatten(bindings, &full, out)?,
+                    value => out.push((full, value)),
+                }
+            }
+            Binding::Inherit { .. } => {
+                return unsupported(format!("unsupported `{}` in attribute set", binding))
+            }
+        }
+    }
+    Ok(())
+}
+
+impl Scope {
+    fn binding(&mut self, binding: Binding) -> Result<(), Error> {
+        match binding {
+            Binding::Inherit {
+                from: Some(from),
+                names,
+            } => {
+                let path = match from.attr_path() {
+                    Some(path) => path.join("."),
+                    None => return unsupported(format!("unsupported inherit source `{}`", from)),
+                };
+                for name in &names {
+                    self.inherits
+                        .insert(attr_name(name)?.to_string(), path.clone());
+                }
+            }
+            Binding::Inherit { from: None, .. } => {
+                return unsupported(format!(
+                    "unsupported `{}` in let block, expected `inherit (source) name;`",
+                    binding
+                ))
+            }
+            Binding::Attr { path, value } => {
+                let name = match path.as_slice() {
+                    [name] => attr_name(name)?.to_string(),
+                    _ => {
+                        return unsupported(format!(
+                            "unsupported let binding `{}`",
+                            Binding::Attr { path, value }
+                        ))
+                    }
+                };
+                if !is_bee(&value) {
+                    self.variables.insert(name, value);
+                    return Ok(());
+                }
+                let bee = self.bee(&value).map_err(|error| {
+                    Error::Unsupported(format!("let binding `{}`: {}", name, error))
+                })?;
+                self.bees.insert(name, bee);
+            }
+        }
+        Ok(())
+    }
+
+    fn bee(&self, value: &Expr) -> Result<Bee, Error> {
+        let bindings = match value {
+            Expr::AttrSet {
+                recursive: false,
+                bindings,
+            } => bindings,
+            value => {
+                return unsupported(format!(
+                    "expected a `{{ bee = {{ ... }}; }}` attribute set, found `{}`",
+                    value
+                ))
+            }
+        };
+        let mut attrs = Vec::new();
+        flatten(bindings, &[], &mut attrs)?;
+        let mut colmena = None;
+        let mut darwin = None;
+        let mut home = None;
+        let mut pkgs = None;
+        let mut system = None;
+        let mut wsl = None;
+        for (path, value) in attrs {
+            match path.join(".").as_str() {
+                "bee.colmena" => colmena = Some(self.path(value)?),
+                "bee.darwin" => darwin = Some(self.path(value)?),
+                "bee.home" => home = Some(self.path(value)?),
+                "bee.pkgs" => pkgs = Some(self.path(value)?),
+                "bee.system" => system = Some(string(value)?),
+                "bee.wsl" => wsl = Some(self.path(value)?),
+                path => return unsupported(format!("unsupported bee attribute `{}`", path)),
+            }
+        }
+        match (pkgs, system) {
+            (Some(pkgs), Some(system)) => {
+                let bee = Bee {
+                    colmena,
+                    darwin,
+                    home,
+                    pkgs,
+                    system,
+                    wsl,
+                };
+                match bee.validate() {
+                    Ok(()) => Ok(bee),
+                    Err(error) => unsupported(error.to_string()),
+                }
+            }
+            (None, _) => unsupported(String::from("missing `bee.pkgs`")),
+            (_, None) => unsupported(String::from("missing `bee.system`")),
+        }
+    }
+
+    /// An attribute path rooted in an argument or an inherit.
+    fn path(&self, expr: &Expr) -> Result<NixValue, Error> {
+        let (head, rest) = split_path(expr)?;
+        let first = if self.arguments.contains(head) {
+            NixValue::argument(head)
+        } else if let Some(path) = self.inherits.get(head) {
+            NixValue::inherit(path, head)
+        } else {
+            return unsupported(format!("unknown identifier `{}` in `{}`", head, expr));
+        };
+        Ok(NixValue::select1(first, rest))
+    }
+
+    fn import(&self, expr: &Expr) -> Result<ConfigurationType, Error> {
+        if let Expr::Ident(name) = expr {
+            if let Some(bee) = self.bees.get(name) {
+                return match Import::from_bee(name, bee.clone()) {
+                    Ok(import) => Ok(import.into()),
+                    Err(error) => unsupported(error.to_string()),
+                };
+            }
+        }
+        let (head, rest) = match split_path(expr) {
+            Ok((head, _)) if self.variables.contains_key(head) || BUILTINS.contains(&head) => {
+                return Ok(self.value(expr, &Bound::default())?.into())
+            }
+            Ok(path) => path,
+            Err(_) => return Ok(self.value(expr, &Bound::default())?.into()),
+        };
+        let inherit = match self.inherits.get(head) {
+            Some(path) if !rest.is_empty() => Some(Inherit::new(path, head)),
+            _ => None,
+        };
+        Ok(Import {
+            inherit,
+            name: self.path(expr)?,
+        }
+        .into())
+    }
+
+    fn imports(&self, value: &Expr) -> Result<Vec<ConfigurationType>, Error> {
+        match value {
+            Expr::List(items) => items
+                .iter()
+                .map(|item| match item {
+                    Expr::AttrSet { .. } => match inline(item) {
+                        Ok(inline) => Ok(inline.into()),
+                        Err(_) => Ok(self.value(item, &Bound::default())?.into()),
+                    },
+                    item => self.import(item),
+                })
+                .collect(),
+            value => unsupported(format!("expected a list of imports, found `{}`", value)),
+        }
+    }
+
+    fn configurations(
+        &self,
+        name: &str,
+        attrs: &[(Vec<String>, &Expr)],
+    ) -> Result<Configurations, Error> {
+        let mut imports = None;
+        for (path, value) in attrs {
+            match path.join(".").as_str() {
+                "imports" if imports.is_some() => {
+                    return unsupported(String::from("duplicate attribute `imports`"))
+                }
+                "imports" => imports = Some(self.imports(value)?),
+                path => return unsupported(format!("unsupported attribute `{}`", path)),
+            }
+        }
+        match imports {
+            Some(configurations) => Ok(Configurations {
+                configurations,
+                name: name.to_string(),
+            }),
+            None => unsupported(String::from("missing `imports`")),
+        }
+    }
+}
+
+impl Scope {
+    /// A name used in an expression, hoisting what it refers to from the file.
+    fn ident(&self, name: &str, bound: &Bound) -> Result<NixValue, Error> {
+        if bound.contains(name) {
+            return Ok(NixValue::Ident(name.to_string()));
+        }
+        if self.arguments.contains(name) {
+            return Ok(NixValue::argument(name));
+        }
+        if let Some(path) = self.inherits.get(name) {
+            return Ok(NixValue::inherit(path, name));
+        }
+        if let Some(expr) = self.variables.get(name) {
+            if let Some(variable) = &bound.variable {
+                return unsupported(format!(
+                    "let binding `{}` refers to let binding `{}`",
+                    variable, name
+                ));
+            }
+            let bound = Bound {
+                names: vec![name.to_string()],
+                variable: Some(name.to_string()),
+                with: false,
+            };
+            return Ok(NixValue::Variable {
+                name: name.to_string(),
+                value: Box::new(self.value(expr, &bound)?),
+            });
+        }
+        match name {
+            "null" => Ok(NixValue::Null),
+            "true" => Ok(NixValue::Bool(true)),
+            "false" => Ok(NixValue::Bool(false)),
+            name if bound.with || BUILTINS.contains(&name) => Ok(NixValue::Ident(name.to_string())),
+            name => unsupported(format!("unknown identifier `{}`", name)),
+        }
+    }
+
+    /// Any expression `NixValue` can hold, such as `./extra.nix` or
+    /// `import ./extra.nix inputs`.
+    fn value(&self, expr: &Expr, bound: &Bound) -> Result<NixValue, Error> {
+        let value = match expr {
+            Expr::Ident(name) => self.ident(name, bound)?,
+            Expr::Int(value) => NixValue::Int(*value),
+            Expr::Float(value) => NixValue::Float(*value),
+            Expr::Unary {
+                op: UnaryOp::Negate,
+                expr,
+            } => match &**expr {
+                Expr::Int(value) => NixValue::Int(-value),
+                Expr::Float(value) => NixValue::Float(-value),
+                _ => return unsupported(format!("unsupported expression `{}`", expr)),
+            },
+            Expr::String(parts) => NixValue::String(
+                parts
+                    .iter()
+                    .map(|part| match part {
+                        StrPart::Literal(literal) => Ok(StringPart::Literal(literal.clone())),
+                        StrPart::Interpolation(expr) => {
+                            Ok(StringPart::Interpolation(self.value(expr, bound)?))
+                        }
+                    })
+                    .collect::<Result<_, Error>>()?,
+            ),
+            Expr::Path(path) => NixValue::Path(path.clone()),
+            Expr::SearchPath(path) => NixValue::Path(format!("<{}>", path)),
+            Expr::List(items) => NixValue::List(
+                items
+                    .iter()
+                    .map(|item| self.value(item, bound))
+                    .collect::<Result<_, _>>()?,
+            ),
+            Expr::AttrSet {
+                recursive,
+                bindings,
+            } => {
+                let mut attrs = Vec::new();
+                flatten(bindings, &[], &mut attrs)?;
+                let bound = match recursive {
+                    true => bound.with_names(attrs.iter().map(|(path, _)| path[0].as_str())),
+                    false => bound.clone(),
+                };
+                let attrs = attrs
+                    .into_iter()
+                    .map(|(path, expr)| Ok((path, self.value(expr, &bound)?)))
+                    .collect::<Result<_, Error>>()?;
+                match recursive {
+                    true => NixValue::RecAttrSet(attrs),
+                    false => NixValue::AttrSet(attrs),
+                }
+            }
+            Expr::Select {
+                expr,
+                path,
+                default: None,
+            } => {
+                let names = path.iter().map(attr_name).collect::<Result<Vec<_>, _>>()?;
+                NixValue::select1(self.value(expr, bound)?, names)
+            }
+            Expr::Apply { function, argument } => {
+                NixValue::apply(self.value(function, bound)?, self.value(argument, bound)?)
+            }
+            Expr::Lambda {
+                param: Param::Ident(name),
+                body,
+            } => NixValue::Lambda {
+                param: value::Param::Ident(name.clone()),
+                body: Box::new(self.value(body, &bound.with_names([name.as_str()]))?),
+            },
+            Expr::Lambda {
+                param:
+                    Param::Pattern {
+                        formals,
+                        ellipsis,
+                        bind: None,
+                    },
+                body,
+            } if formals.iter().all(|formal| formal.default.is_none()) => {
+                let names: Vec<String> = formals.iter().map(|formal| formal.name.clone()).collect();
+                let bound = bound.with_names(names.iter().map(String::as_str));
+                NixValue::Lambda {
+                    param: value::Param::Pattern {
+                        formals: names,
+                        ellipsis: *ellipsis,
+                    },
+                    body: Box::new(self.value(body, &bound)?),
+                }
+            }
+            Expr::Let { bindings, body } => {
+                let mut names = Vec::new();
+                for binding in bindings {
+                    match binding {
+                        Binding::Attr { path, value }
+                            if path.len() == 1 && matches!(path[0], AttrName::Ident(_)) =>
+                        {
+                            names.push((attr_name(&path[0])?, value))
+                        }
+                        binding => return unsupported(format!("unsupported `{}`", binding)),
+                    }
+                }
+                let bound = bound.with_names(names.iter().map(|(name, _)| *name));
+                NixValue::Let {
+                    bindings: names
+                        .into_iter()
+                        .map(|(name, expr)| Ok((name.to_string(), self.value(expr, &bound)?)))
+                        .collect::<Result<_, Error>>()?,
+                    body: Box::new(self.value(body, &bound)?),
+                }
+            }
+            Expr::With { namespace, body } => {
+                let mut with = bound.clone();
+                with.with = true;
+                NixValue::With {
+                    namespace: Box::new(self.value(namespace, bound)?),
+                    body: Box::new(self.value(body, &with)?),
+                }
+            }
+            expr => return unsupported(format!("unsupported expression `{}`", expr)),
+        };
+        Ok(value)
+    }
+}
+
+/// Whether a `let` binding holds a bee, `{ bee = { ... }; }` or
+/// `{ bee.system = ...; }`.
+fn is_bee(value: &Expr) -> bool {
+    match value {
+        Expr::AttrSet {
+            recursive: false,
+            bindings,
+        } => bindings.iter().any(|binding| {
+            matches!(binding, Binding::Attr { path, .. }
+                if path.first().and_then(AttrName::as_str) == Some("bee"))
+        }),
+        _ => false,
+    }
+}
+
+/// Split `head.a."b.c"` into `head` and the remaining names `a` and `b.c`.
+fn split_path(expr: &Expr) -> Result<(&str, Vec<&str>), Error> {
+    let (head, path) = match expr {
+        Expr::Ident(head) => (head, &[][..]),
+        Expr::Select {
+            expr,
+            path,
+            default: None,
+        } => match &**expr {
+            Expr::Ident(head) => (head, &path[..]),
+            _ => return unsupported(format!("expected an attribute path, found `{}`", expr)),
+        },
+        expr => return unsupported(format!("expected an attribute path, found `{}`", expr)),
+    };
+    let mut rest = Vec::new();
+    for name in path {
+        rest.push(attr_name(name)?);
+    }
+    Ok((head.as_str(), rest))
+}
+
+fn string(expr: &Expr) -> Result<String, Error> {
+    match expr {
+        Expr::String(parts) => match parts.as_slice() {
+            [] => Ok(String::new()),
+            [StrPart::Literal(literal)] => Ok(literal.clone()),
+            _ => unsupported(format!("unsupported interpolated string `{}`", expr)),
+        },
+        expr => unsupported(format!("expected a string, found `{}`", expr)),
+    }
+}
+
+/// An inline module, only literal values.
+fn inline(expr: &Expr) -> Result<Inline, Error> {
+    let mut inline = Inline::new();
+    for (path, expr) in attr_set(expr)? {
+        inline.attrs.push((path, value(expr)?));
+    }
+    Ok(inline)
+}
+
+fn value(expr: &Expr) -> Result<NixValue, Error> {
+    match expr {
+        Expr::Ident(name) if name == "null" => Ok(NixValue::Null),
+        Expr::Ident(name) if name == "true" => Ok(NixValue::Bool(true)),
+        Expr::Ident(name) if name == "false" => Ok(NixValue::Bool(false)),
+        Expr::Int(value) => Ok(NixValue::Int(*value)),
+        Expr::Float(value) => Ok(NixValue::Float(*value)),
+        Expr::String(_) => Ok(string(expr)?.into()),
+        Expr::Path(path) => Ok(NixValue::Path(path.clone())),
+        Expr::List(items) => Ok(NixValue::List(
+            items.iter().map(value).collect::<Result<_, _>>()?,
+        )),
+        Expr::AttrSet { .. } => Ok(inline(expr)?.into()),
+        expr => unsupported(format!("unsupported value `{}` in inline module", expr)),
+    }
+}
+
+fn attr_set(body: &Expr) -> Result<Attrs<'_>, Error> {
+    match body {
+        Expr::AttrSet {
+            recursive: false,
+            bindings,
+        } => {
+            let mut attrs = Vec::new();
+            flatten(bindings, &[], &mut attrs)?;
+            Ok(attrs)
+        }
+        body => unsupported(format!("expected an attribute set, found `{}`", body)),
+    }
+}
+
+pub(crate) fn configurations(name: &str, input: &str) -> Result<Configurations, Error> {
+    let (scope, body) = scope(crate::nix::parse(input)?)?;
+    let attrs = attr_set(&body)?;
+    scope.configurations(name, &attrs)
+}
+
+pub(crate) fn nixos_configurations(input: &str) -> Result<NixosConfigurations, Error> {
+    let (scope, body) = scope(crate::nix::parse(input)?)?;
+    let mut hosts: Vec<(String, Attrs)> = Vec::new();
+    for (mut path, value) in attr_set(&body)? {
+        let host = path.remove(0);
+        match hosts.iter_mut().find(|(name, _)| *name == host) {
+            Some((_, attrs)) => attrs.push((path, value)),
+            None => hosts.push((host, vec![(path, value)])),
+        }
+    }
+    let mut configurations = Vec::new();
+    for (host, attrs) in hosts {
+        let host_configurations = scope
+            .configurations(&host, &attrs)
+            .map_err(|error| Error::Unsupported(format!("host `{}`: {}", host, error)))?;
+        configurations.push(host_configurations);
+    }
+    Ok(NixosConfigurations(configurations))
+}
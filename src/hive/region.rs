@@ -0,0 +1,260 @@
+use crate::hive::cell::File;
+use genco::prelude::*;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const BEGIN: &str = "# honey:begin";
+const END: &str = "# honey:end";
+
+/// Error returned when the regions of a file on disk can not be kept.
+#[derive(Debug)]
+pub enum Error {
+    Io(PathBuf, io::Error),
+    /// A `# honey:begin` without its `# honey:end` or the other way around,
+    /// at the given line.
+    Unbalanced(PathBuf, usize),
+    /// Two regions of the file share a name.
+    Duplicate(PathBuf, String),
+    /// A region holding code the regenerated file has no place for.
+    Dropped(PathBuf, String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(path, error) => write!(f, "{}: {}", path.display(), error),
+            Error::Unbalanced(path, line) => {
+                write!(f, "{}:{}: unbalanced region marker", path.display(), line)
+            }
+            Error::Duplicate(path, name) => {
+                write!(f, "{}: region `{}` appears twice", path.display(), name)
+            }
+            Error::Dropped(path, name) => write!(
+                f,
+                "{}: region `{}` is no longer generated, move its contents first",
+                path.display(),
+                name
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(_, error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// An empty region for hand-written code, kept as it is on disk when the
+/// file is generated again.
+///
+/// ```
+/// use genco::prelude::*;
+/// use honey::hive::*;
+///
+/// let configurations = Configurations::builder("machine00")
+///     .disko(false)
+///     .home(false)
+///     .region("machine00")
+///     .build();
+///
+/// let toks = quote!($configurations);
+///
+/// assert_eq!(
+///     vec![
+///         "{",
+///         "    cell,",
+///         "    ...",
+///         "}:",
+///         "",
+///         "{",
+///         "    imports = [",
+///         "        cell.hardwareProfiles.machine00",
+///         "        cell.nixosModules.machine00",
+///         "        cell.nixosProfiles.machine00",
+///         "        # honey:begin machine00",
+///         "        # honey:end",
+///         "    ];",
+///         "}",
+///     ],
+///     toks.to_file_vec()?
+/// );
+/// # Ok::<_, genco::fmt::Error>(())
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Region {
+    pub name: String,
+}
+
+impl Region {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: String::from(name),
+        }
+    }
+}
+
+impl FormatInto<Nix> for Region {
+    fn format_into(self, tokens: &mut Tokens<Nix>) {
+        tokens.append(format!("{} {}", BEGIN, self.name));
+        tokens.push();
+        tokens.append(END);
+    }
+}
+
+enum Marker<'a> {
+    Begin(&'a str),
+    End,
+}
+
+fn marker(line: &str) -> Option<Marker<'_>> {
+    let line = line.trim();
+    if line == END {
+        return Some(Marker::End);
+    }
+    let name = line.strip_prefix(BEGIN)?;
+    if !name.starts_with(char::is_whitespace) {
+        return None;
+    }
+    Some(Marker::Begin(name.trim()))
+}
+
+/// The lines between the markers of every region, by name and in order.
+fn regions<'a>(path: &Path, contents: &'a str) -> Result<Vec<(&'a str, Vec<&'a str>)>, Error> {
+    let mut regions: Vec<(&str, Vec<&str>)> = Vec::new();
+    let mut open: Option<(&str, Vec<&str>)> = None;
+    for (i, line) in contents.lines().enumerate() {
+        match (marker(line), &mut open) {
+            (Some(Marker::Begin(name)), None) => {
+                if regions.iter().any(|(other, _)| *other == name) {
+                    return Err(Error::Duplicate(path.to_path_buf(), name.to_string()));
+                }
+                open = Some((name, Vec::new()));
+            }
+            (Some(Marker::End), Some(_)) => regions.extend(open.take()),
+            (Some(_), _) => return Err(Error::Unbalanced(path.to_path_buf(), i + 1)),
+            (None, Some((_, lines))) => lines.push(line),
+            (None, None) => (),
+        }
+    }
+    if open.is_some() {
+        return Err(Error::Unbalanced(
+            path.to_path_buf(),
+            contents.lines().count(),
+        ));
+    }
+    Ok(regions)
+}
+
+/// Whether `contents` hold a region.
+pub(crate) fn any(contents: &str) -> bool {
+    contents.lines().any(|line| marker(line).is_some())
+}
+
+/// Names of the regions of the file at `path`, none when it does not exist.
+///
+/// ```
+/// use honey::hive::region;
+///
+/// let path = std::env::temp_dir().join("honey-region-names-doctest.nix");
+/// std::fs::write(&path, "[\n    # honey:begin machine00\n    ./extra.nix\n    # honey:end\n]\n")?;
+///
+/// assert_eq!(vec!["machine00"], region::names(&path)?);
+///
+/// std::fs::remove_file(&path)?;
+/// assert!(region::names(&path)?.is_empty());
+/// # Ok::<_, Box<dyn std::error::Error>>(())
+/// ```
+pub fn names(path: &Path) -> Result<Vec<String>, Error> {
+    let existing = match std::fs::read_to_string(path) {
+        Ok(existing) => existing,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(error) => return Err(Error::Io(path.to_path_buf(), error)),
+    };
+    Ok(regions(path, &existing)?
+        .into_iter()
+        .map(|(name, _)| name.to_string())
+        .collect())
+}
+
+/// The `generated` contents of the file at `path`, with its regions filled
+/// in from the `existing` contents. A region of `existing` that holds
+/// anything but whitespace must still be generated.
+///
+/// ```
+/// use honey::hive::region::preserve;
+/// use std::path::Path;
+///
+/// let existing = "[\n    a\n    # honey:begin custom\n    ./extra.nix\n    # honey:end\n]\n";
+/// let generated = "[\n    a\n    b\n    # honey:begin custom\n    # honey:end\n]\n";
+///
+/// assert_eq!(
+///     "[\n    a\n    b\n    # honey:begin custom\n    ./extra.nix\n    # honey:end\n]\n",
+///     preserve(Path::new("imports.nix"), existing, generated)?
+/// );
+///
+/// let error = preserve(Path::new("imports.nix"), existing, "[ a ]\n").unwrap_err();
+/// assert_eq!(
+///     "imports.nix: region `custom` is no longer generated, move its contents first",
+///     error.to_string()
+/// );
+/// # Ok::<_, honey::hive::region::Error>(())
+/// ```
+pub fn preserve(path: &Path, existing: &str, generated: &str) -> Result<String, Error> {
+    let kept = regions(path, existing)?;
+    let fresh = regions(path, generated)?;
+    for (name, lines) in &kept {
+        let blank = lines.iter().all(|line| line.trim().is_empty());
+        if !blank && !fresh.iter().any(|(other, _)| other == name) {
+            return Err(Error::Dropped(path.to_path_buf(), name.to_string()));
+        }
+    }
+    let mut contents = String::new();
+    let mut skipping = false;
+    for line in generated.lines() {
+        match marker(line) {
+            Some(Marker::Begin(name)) => {
+                contents.push_str(line);
+                contents.push('\n');
+                if let Some((_, lines)) = kept.iter().find(|(other, _)| *other == name) {
+                    for line in lines {
+                        contents.push_str(line);
+                        contents.push('\n');
+                    }
+                    skipping = true;
+                }
+            }
+            Some(Marker::End) => {
+                skipping = false;
+                contents.push_str(line);
+                contents.push('\n');
+            }
+            None if skipping => (),
+            None => {
+                contents.push_str(line);
+                contents.push('\n');
+            }
+        }
+    }
+    if !generated.ends_with('\n') {
+        contents.pop();
+    }
+    Ok(contents)
+}
+
+/// Fill in the regions of every file from the file on disk, if any.
+pub fn preserve_files(files: &mut [File]) -> Result<(), Error> {
+    for file in files {
+        let existing = match std::fs::read_to_string(&file.path) {
+            Ok(existing) => existing,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => continue,
+            Err(error) => return Err(Error::Io(file.path.clone(), error)),
+        };
+        file.contents = preserve(&file.path, &existing, &file.contents)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,206 @@
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Error returned for an input name the registry does not know.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Error {
+    pub name: String,
+    /// A known name close to `name`.
+    pub suggestion: Option<String>,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown input `{}`", self.name)?;
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, ", did you mean `{}`?", suggestion)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// A flake input.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct Entry {
+    pub url: String,
+    /// `inputs.<input>.follows = "<target>"`, e.g. `nixpkgs` following
+    /// `nixos-23-05`.
+    #[serde(default)]
+    pub follows: BTreeMap<String, String>,
+}
+
+impl Entry {
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: String::from(url),
+            follows: BTreeMap::new(),
+        }
+    }
+
+    pub fn follows(mut self, input: &str, target: &str) -> Self {
+        self.follows
+            .insert(String::from(input), String::from(target));
+        self
+    }
+}
+
+/// Release version of a conventional name, `23.05` for `nixos-23-05`.
+fn release<'a>(name: &'a str, prefix: &str) -> Option<&'a str> {
+    let version = name.strip_prefix(prefix)?;
+    let bytes = version.as_bytes();
+    let valid = bytes.len() == 5
+        && bytes[2] == b'-'
+        && bytes[..2].iter().all(u8::is_ascii_digit)
+        && bytes[3..].iter().all(u8::is_ascii_digit)
+        && matches!(&version[3..], "05" | "11");
+    valid.then_some(version)
+}
+
+/// Edit distance between two names, for suggestions.
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut previous = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let current = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                previous
+            } else {
+                1 + previous.min(row[j]).min(current)
+            };
+            previous = current;
+        }
+    }
+    row[b.len()]
+}
+
+/// Flake inputs by name. Besides the registered names, `nixos-YY-MM` and
+/// `home-YY-MM` resolve to the nixpkgs and home-manager release branches,
+/// with home-manager following the matching nixpkgs.
+///
+/// ```
+/// use honey::hive::registry::{Entry, Registry};
+///
+/// let mut registry = Registry::default();
+/// registry.insert("nixos-hardware-fork", Entry::new("github:example/nixos-hardware"));
+///
+/// let home = registry.resolve("home-23-05")?;
+/// assert_eq!("github:nix-community/home-manager/release-23.05", home.url);
+/// assert_eq!(Some(&String::from("nixos-23-05")), home.follows.get("nixpkgs"));
+///
+/// assert!(registry.resolve("nixos-hardware-fork").is_ok());
+///
+/// assert_eq!(
+///     "unknown input `nixos-2305`, did you mean `nixos-23-05`?",
+///     registry.resolve("nixos-2305").unwrap_err().to_string()
+/// );
+/// assert_eq!(
+///     "unknown input `home-manger`, did you mean `home-manager`?",
+///     registry.resolve("home-manger").unwrap_err().to_string()
+/// );
+/// # Ok::<_, honey::hive::registry::Error>(())
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Registry {
+    pub entries: BTreeMap<String, Entry>,
+}
+
+impl Default for Registry {
+    /// The inputs honey itself generates references to.
+    fn default() -> Self {
+        let mut registry = Self::new();
+        let nixpkgs = |entry: Entry| entry.follows("nixpkgs", "nixpkgs");
+        registry.insert("colmena", nixpkgs(Entry::new("github:zhaofengli/colmena")));
+        registry.insert("darwin", nixpkgs(Entry::new("github:lnl7/nix-darwin")));
+        registry.insert("disko", nixpkgs(Entry::new("github:nix-community/disko")));
+        registry.insert("hive", Entry::new("github:divnix/hive"));
+        registry.insert(
+            "home-manager",
+            nixpkgs(Entry::new("github:nix-community/home-manager")),
+        );
+        registry.insert("nixos-hardware", Entry::new("github:NixOS/nixos-hardware"));
+        registry.insert(
+            "nixos-unstable",
+            Entry::new("github:NixOS/nixpkgs/nixos-unstable"),
+        );
+        registry.insert(
+            "nixos-wsl",
+            nixpkgs(Entry::new("github:nix-community/NixOS-WSL")),
+        );
+        registry.insert("nixpkgs", Entry::new("github:NixOS/nixpkgs"));
+        registry.insert(
+            "nixpkgs-darwin",
+            Entry::new("github:NixOS/nixpkgs/nixpkgs-unstable"),
+        );
+        registry.insert("std", nixpkgs(Entry::new("github:divnix/std")));
+        registry
+    }
+}
+
+impl Registry {
+    /// A registry knowing only the `nixos-YY-MM` and `home-YY-MM` conventions.
+    pub fn new() -> Self {
+        Self {
+            entries: BTreeMap::new(),
+        }
+    }
+
+    pub fn insert(&mut self, name: &str, entry: Entry) {
+        self.entries.insert(String::from(name), entry);
+    }
+
+    pub fn resolve(&self, name: &str) -> Result<Entry, Error> {
+        if let Some(entry) = self.entries.get(name) {
+            return Ok(entry.clone());
+        }
+        if let Some(version) = release(name, "nixos-") {
+            return Ok(Entry::new(&format!(
+                "github:NixOS/nixpkgs/nixos-{}",
+                version.replace('-', ".")
+            )));
+        }
+        if let Some(version) = release(name, "home-") {
+            return Ok(Entry::new(&format!(
+                "github:nix-community/home-manager/release-{}",
+                version.replace('-', ".")
+            ))
+            .follows("nixpkgs", &format!("nixos-{}", version)));
+        }
+        Err(Error {
+            name: String::from(name),
+            suggestion: self.suggest(name),
+        })
+    }
+
+    /// Check that `name` resolves.
+    pub fn check(&self, name: &str) -> Result<(), Error> {
+        self.resolve(name).map(|_| ())
+    }
+
+    fn suggest(&self, name: &str) -> Option<String> {
+        // `nixos-2305` and `home-23.05` for the conventional names.
+        for prefix in ["nixos-", "home-"] {
+            if let Some(version) = name.strip_prefix(prefix) {
+                let digits: String = version.chars().filter(char::is_ascii_digit).collect();
+                if digits.len() == 4 {
+                    let candidate = format!("{}{}-{}", prefix, &digits[..2], &digits[2..]);
+                    if release(&candidate, prefix).is_some() {
+                        return Some(candidate);
+                    }
+                }
+            }
+        }
+        self.entries
+            .keys()
+            .map(|known| (distance(name, known), known))
+            .filter(|(distance, _)| *distance <= 2)
+            .min()
+            .map(|(_, known)| known.clone())
+    }
+}
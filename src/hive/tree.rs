@@ -0,0 +1,391 @@
+use crate::hive::cell::File;
+use crate::hive::*;
+use crate::nix::{Binding, Expr, NixValue};
+use genco::prelude::*;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Error returned when the cells of a hive can not be read.
+#[derive(Debug)]
+pub enum Error {
+    Io(PathBuf, io::Error),
+    /// A single-file block is not valid Nix.
+    Syntax(PathBuf, crate::nix::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(path, error) => write!(f, "{}: {}", path.display(), error),
+            Error::Syntax(path, error) => {
+                write!(f, "{}: syntax error at {}", path.display(), error)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(_, error) => Some(error),
+            Error::Syntax(_, error) => Some(error),
+        }
+    }
+}
+
+/// What a dangling reference is missing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Missing {
+    Cell,
+    Block,
+    Target,
+}
+
+/// An import referring to a cell block target that does not exist.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Dangling {
+    /// Host or user whose configuration holds the import.
+    pub host: String,
+    /// The import as written, e.g. `cell.hardwareProfiles.machine07`.
+    pub import: String,
+    pub missing: Missing,
+}
+
+impl fmt::Display for Dangling {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let what = match self.missing {
+            Missing::Cell => "cell",
+            Missing::Block => "block",
+            Missing::Target => "target",
+        };
+        write!(
+            f,
+            "host `{}`: `{}` does not exist (missing {})",
+            self.host, self.import, what
+        )
+    }
+}
+
+/// The targets of every block of a hive, as laid out below `cells/`.
+///
+/// A block is either a single file `<block>.nix` (or `<block>/default.nix`)
+/// holding an attribute set, or a haumea directory `<block>/` whose
+/// `<target>.nix` files and `<target>/` directories are the targets.
+///
+/// ```
+/// use honey::hive::*;
+/// use honey::hive::tree::{Missing, Tree};
+///
+/// let mut tree = Tree::default();
+/// tree.insert("hosts", "hardwareProfiles", ["machine00"]);
+/// tree.insert_opaque("hosts", "nixosModules");
+///
+/// let nixos_configurations = NixosConfigurations::new1("machine", 2, None, "nixos-23-05", "x86_64-linux")?;
+///
+/// let dangling = tree.check_configurations("hosts", &nixos_configurations.0);
+///
+/// let found: Vec<(&str, &str, Missing)> = dangling
+///     .iter()
+///     .map(|dangling| (dangling.host.as_str(), dangling.import.as_str(), dangling.missing))
+///     .collect();
+///
+/// assert_eq!(
+///     vec![
+///         ("machine00", "cell.diskoConfigurations.machine00", Missing::Block),
+///         ("machine00", "cell.nixosProfiles.machine00", Missing::Block),
+///         ("machine01", "cell.diskoConfigurations.machine01", Missing::Block),
+///         ("machine01", "cell.hardwareProfiles.machine01", Missing::Target),
+///         ("machine01", "cell.nixosProfiles.machine01", Missing::Block),
+///     ],
+///     found
+/// );
+/// assert_eq!(
+///     "host `machine01`: `cell.hardwareProfiles.machine01` does not exist (missing target)",
+///     dangling[3].to_string()
+/// );
+///
+/// tree.insert("common", "nixosProfiles", ["base"]);
+///
+/// let imports = [
+///     Import::cells_nixos_profiles("common", "base"),
+///     Import::cells_nixos_profiles("common", "server"),
+///     Import::cells_nixos_modules("shared", "ssh"),
+/// ];
+/// let dangling = tree.check("hosts", "machine00", &imports);
+///
+/// assert_eq!(
+///     vec![
+///         "host `machine00`: `inputs.cells.common.nixosProfiles.server` does not exist (missing target)",
+///         "host `machine00`: `inputs.cells.shared.nixosModules.ssh` does not exist (missing cell)",
+///     ],
+///     dangling.iter().map(ToString::to_string).collect::<Vec<_>>()
+/// );
+/// # Ok::<_, honey::hive::configurations::Error>(())
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Tree {
+    /// Targets by cell and block. `None` when the block is not a literal
+    /// attribute set, every target of it is then assumed to exist.
+    pub cells: BTreeMap<String, BTreeMap<String, Option<BTreeSet<String>>>>,
+}
+
+/// Names haumea does not export: hidden files and `_`-prefixed private ones.
+fn exported(name: &str) -> bool {
+    !name.starts_with('.') && !name.starts_with('_')
+}
+
+fn entries(path: &Path) -> Result<Vec<(String, PathBuf)>, Error> {
+    let io_error = |error| Error::Io(path.to_path_buf(), error);
+    let mut entries = Vec::new();
+    for entry in std::fs::read_dir(path).map_err(io_error)? {
+        let entry = entry.map_err(io_error)?;
+        if let Some(name) = entry.file_name().to_str() {
+            if exported(name) {
+                entries.push((name.to_string(), entry.path()));
+            }
+        }
+    }
+    entries.sort();
+    Ok(entries)
+}
+
+/// Top-level attribute names of a single-file block, below the argument
+/// pattern and `let` block.
+fn targets(path: &Path, input: &str) -> Result<Option<BTreeSet<String>>, Error> {
+    let mut expr =
+        crate::nix::parse(input).map_err(|error| Error::Syntax(path.to_path_buf(), error))?;
+    loop {
+        expr = match expr {
+            Expr::Lambda { body, .. } | Expr::Let { body, .. } => *body,
+            _ => break,
+        };
+    }
+    let Expr::AttrSet { bindings, .. } = expr else {
+        return Ok(None);
+    };
+    let mut targets = BTreeSet::new();
+    for binding in &bindings {
+        let names = match binding {
+            Binding::Attr { path, .. } => &path[..1],
+            Binding::Inherit { names, .. } => &names[..],
+        };
+        match names
+            .iter()
+            .map(|name| name.as_str())
+            .collect::<Option<Vec<_>>>()
+        {
+            Some(names) => targets.extend(names.into_iter().map(String::from)),
+            // `${name} = ...;`, the targets are only known to Nix.
+            None => return Ok(None),
+        }
+    }
+    Ok(Some(targets))
+}
+
+fn read_targets(path: &Path) -> Result<Option<BTreeSet<String>>, Error> {
+    let input =
+        std::fs::read_to_string(path).map_err(|error| Error::Io(path.to_path_buf(), error))?;
+    targets(path, &input)
+}
+
+/// A cell block target an import refers to.
+struct Reference {
+    /// `None` for the cell the import is written to.
+    cell: Option<String>,
+    block: String,
+    target: String,
+    /// The import as written.
+    rendered: String,
+}
+
+/// The target of an import of the form `cell.<block>.<target>` or
+/// `inputs.cells.<cell>.<block>.<target>`, the cell possibly inherited.
+fn reference(import: &Import) -> Option<Reference> {
+    let NixValue::Select { value, path } = &import.name else {
+        return None;
+    };
+    let path: Vec<&str> = path.iter().map(String::as_str).collect();
+    let (cell, block, target) = match (value.as_ref(), path.as_slice()) {
+        (NixValue::Argument(argument), [block, target, ..]) if argument == "cell" => {
+            (None, *block, *target)
+        }
+        (NixValue::Argument(argument), ["cells", cell, block, target, ..])
+            if argument == "inputs" =>
+        {
+            (Some(*cell), *block, *target)
+        }
+        (NixValue::Inherit { path, name }, [block, target, ..]) if path == "inputs.cells" => {
+            (Some(name.as_str()), *block, *target)
+        }
+        _ => return None,
+    };
+    let written = match cell {
+        Some(cell) => NixValue::select1(
+            NixValue::Ident(String::from("inputs")),
+            ["cells", cell, block, target],
+        ),
+        None => NixValue::select1(NixValue::Ident(String::from("cell")), [block, target]),
+    };
+    let rendered = quote!($written).to_string().ok()?;
+    Some(Reference {
+        cell: cell.map(String::from),
+        block: block.to_string(),
+        target: target.to_string(),
+        rendered,
+    })
+}
+
+impl Tree {
+    /// Scan `cells/` below the hive `root`. A hive without cells yields an
+    /// empty tree.
+    pub fn load(root: &Path) -> Result<Self, Error> {
+        let mut tree = Self::default();
+        let cells = root.join("cells");
+        if !cells.is_dir() {
+            return Ok(tree);
+        }
+        for (cell, path) in entries(&cells)? {
+            if !path.is_dir() {
+                continue;
+            }
+            let blocks = tree.cells.entry(cell).or_default();
+            for (name, path) in entries(&path)? {
+                if path.is_dir() {
+                    let default = path.join("default.nix");
+                    let targets = if default.is_file() {
+                        read_targets(&default)?
+                    } else {
+                        let mut targets = BTreeSet::new();
+                        for (target, path) in entries(&path)? {
+                            match target.strip_suffix(".nix") {
+                                Some(target) => targets.insert(target.to_string()),
+                                None if path.is_dir() => targets.insert(target),
+                                None => continue,
+                            };
+                        }
+                        Some(targets)
+                    };
+                    blocks.insert(name, targets);
+                } else if let Some(block) = name.strip_suffix(".nix") {
+                    blocks.insert(block.to_string(), read_targets(&path)?);
+                }
+            }
+        }
+        Ok(tree)
+    }
+
+    /// Add a block, replacing what was found on disk.
+    pub fn insert<T, S>(&mut self, cell: &str, block: &str, targets: T)
+    where
+        T: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.cells.entry(cell.to_string()).or_default().insert(
+            block.to_string(),
+            Some(targets.into_iter().map(Into::into).collect()),
+        );
+    }
+
+    /// Add a block whose targets are unknown, such as one computed by a
+    /// function.
+    pub fn insert_opaque(&mut self, cell: &str, block: &str) {
+        self.cells
+            .entry(cell.to_string())
+            .or_default()
+            .insert(block.to_string(), None);
+    }
+
+    /// Lay files about to be written below `root` over the tree, so blocks
+    /// generated in the same run count as existing.
+    pub fn overlay(&mut self, root: &Path, files: &[File]) -> Result<(), Error> {
+        let cells = root.join("cells");
+        for file in files {
+            let Ok(relative) = file.path.strip_prefix(&cells) else {
+                continue;
+            };
+            let parts: Vec<&str> = relative.iter().filter_map(|part| part.to_str()).collect();
+            if let [cell, block] = parts.as_slice() {
+                if let Some(block) = block.strip_suffix(".nix") {
+                    let targets = targets(&file.path, &file.contents)?;
+                    self.cells
+                        .entry(cell.to_string())
+                        .or_default()
+                        .insert(block.to_string(), targets);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn missing(&self, cell: &str, block: &str, target: &str) -> Option<Missing> {
+        let Some(blocks) = self.cells.get(cell) else {
+            return Some(Missing::Cell);
+        };
+        match blocks.get(block) {
+            None => Some(Missing::Block),
+            Some(Some(targets)) if !targets.contains(target) => Some(Missing::Target),
+            Some(_) => None,
+        }
+    }
+
+    /// Every `cell.<block>.<target>` import of `host` written to `cell`, and
+    /// `inputs.cells.<cell>.<block>.<target>` import, the tree lacks.
+    pub fn check<'a, T>(&self, cell: &str, host: &str, imports: T) -> Vec<Dangling>
+    where
+        T: IntoIterator<Item = &'a Import>,
+    {
+        let mut dangling = Vec::new();
+        for import in imports {
+            if let Some(reference) = reference(import) {
+                let cell = reference.cell.as_deref().unwrap_or(cell);
+                if let Some(missing) = self.missing(cell, &reference.block, &reference.target) {
+                    dangling.push(Dangling {
+                        host: host.to_string(),
+                        import: reference.rendered,
+                        missing,
+                    });
+                }
+            }
+        }
+        dangling
+    }
+
+    /// Check the hosts of a `nixosConfigurations` or `darwinConfigurations`
+    /// block written to `cell`.
+    pub fn check_configurations(&self, cell: &str, hosts: &[Configurations]) -> Vec<Dangling> {
+        let mut dangling = Vec::new();
+        for host in hosts {
+            let imports =
+                host.configurations
+                    .iter()
+                    .filter_map(|configuration| match configuration {
+                        ConfigurationType::Import(import) => Some(import),
+                        ConfigurationType::Inline(_)
+                        | ConfigurationType::Region(_)
+                        | ConfigurationType::Value(_)
+                        | ConfigurationType::Dummy => None,
+                    });
+            dangling.extend(self.check(cell, &host.name, imports));
+        }
+        dangling
+    }
+
+    /// Check the users of a `homeConfigurations` block written to `cell`.
+    pub fn check_home_configurations(
+        &self,
+        cell: &str,
+        home_configurations: &HomeConfigurations,
+    ) -> Vec<Dangling> {
+        let mut dangling = Vec::new();
+        for home_configuration in &home_configurations.0 {
+            dangling.extend(self.check(
+                cell,
+                &home_configuration.name,
+                &home_configuration.imports,
+            ));
+        }
+        dangling
+    }
+}
@@ -1,8 +1,10 @@
+use crate::hive::*;
+use crate::nix::NixValue;
 use genco::prelude::*;
 
 pub struct Variable {
     pub name: String,
-    pub value: nix::Tokens,
+    pub value: NixValue,
 }
 
 impl Variable {
@@ -44,29 +46,80 @@ impl Variable {
     /// ```
     pub fn bee<M, N>(name: &str, home_manager: Option<M>, nixpkgs: N, system: &str) -> Self
     where
-        M: Into<nix::Tokens>,
-        N: Into<nix::Tokens>,
+        M: Into<NixValue>,
+        N: Into<NixValue>,
     {
-        Self {
-            name: name.to_string(),
-            value: quote! {
-                {
-                    bee = {
-                        $(if let Some(home_manager) = home_manager {
-                            home = $(home_manager.into());
-                        })
-                        pkgs = $(nixpkgs.into());
-                        system = $(quoted(system));
-                    };
-                }
-            },
+        Self::bee1(name, None::<NixValue>, home_manager, nixpkgs, system)
+    }
+
+    /// Bee with an optional nix-darwin input.
+    ///
+    /// ```
+    /// use genco::prelude::*;
+    /// use honey::hive::*;
+    ///
+    /// let darwin = Some(Inherit::new("inputs", "darwin"));
+    /// let nixpkgs = Inherit::new("inputs", "nixpkgs-darwin");
+    ///
+    /// let bee = Variable::bee1("bee", darwin, None::<Inherit>, nixpkgs, "aarch64-darwin");
+    ///
+    /// let toks = quote!($bee);
+    ///
+    /// assert_eq!(
+    ///     vec![
+    ///         "{",
+    ///         "    inputs,",
+    ///         "    ...",
+    ///         "}:",
+    ///         "",
+    ///         "let",
+    ///         "    inherit (inputs) darwin;",
+    ///         "    inherit (inputs) nixpkgs-darwin;",
+    ///         "    bee = {",
+    ///         "        bee = {",
+    ///         "            darwin = darwin;",
+    ///         "            pkgs = nixpkgs-darwin;",
+    ///         "            system = \"aarch64-darwin\";",
+    ///         "        };",
+    ///         "    };",
+    ///         "in",
+    ///         "",
+    ///         "bee"
+    ///     ],
+    ///     toks.to_file_vec()?
+    /// );
+    /// # Ok::<_, genco::fmt::Error>(())
+    /// ```
+    pub fn bee1<D, M, N>(
+        name: &str,
+        darwin: Option<D>,
+        home_manager: Option<M>,
+        nixpkgs: N,
+        system: &str,
+    ) -> Self
+    where
+        D: Into<NixValue>,
+        M: Into<NixValue>,
+        N: Into<NixValue>,
+    {
+        let mut bee = Bee::new(system, nixpkgs);
+        bee.darwin = darwin.map(Into::into);
+        bee.home = home_manager.map(Into::into);
+        bee.variable(name)
+    }
+}
+
+impl From<Variable> for NixValue {
+    fn from(variable: Variable) -> Self {
+        NixValue::Variable {
+            name: variable.name,
+            value: Box::new(variable.value),
         }
     }
 }
 
 impl FormatInto<Nix> for Variable {
     fn format_into(self, tokens: &mut Tokens<Nix>) {
-        let variable = nix::variable(self.name, self.value);
-        tokens.append(variable);
+        tokens.append(NixValue::from(self));
     }
 }
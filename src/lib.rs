@@ -1 +1,3 @@
 pub mod hive;
+pub mod manifest;
+pub mod nix;
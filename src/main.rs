@@ -0,0 +1,279 @@
+use anyhow::Context;
+use clap::{Parser, Subcommand};
+use honey::hive::cell::{self, Cell};
+use honey::hive::flake::Flake;
+use honey::hive::region;
+use honey::hive::tree::Tree;
+use honey::hive::*;
+use honey::manifest::{Group, Manifest};
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Blocks holding one entry per host next to `nixosConfigurations`.
+const HOST_BLOCKS: [&str; 4] = [
+    "diskoConfigurations",
+    "hardwareProfiles",
+    "nixosModules",
+    "nixosProfiles",
+];
+
+/// Blocks holding one entry per host next to `darwinConfigurations`.
+const DARWIN_HOST_BLOCKS: [&str; 2] = ["darwinModules", "darwinProfiles"];
+
+#[derive(Parser)]
+#[command(name = "honey", version, about = "Generate hive cell blocks")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Write the cell blocks of a fleet, from a manifest or numbered hosts.
+    Generate(Generate),
+}
+
+#[derive(clap::Args)]
+struct Generate {
+    /// Fleet manifest (.toml, .yaml, .yml or .json) to read hosts from.
+    #[arg(long, conflicts_with_all = ["prefix", "number", "start", "step", "width", "suffix", "exclude", "home_manager", "nixpkgs", "system"])]
+    manifest: Option<PathBuf>,
+    /// Cell the blocks are written to, overrides the manifest.
+    #[arg(long, required_unless_present = "manifest")]
+    cell: Option<String>,
+    /// Host name prefix, hosts are named `<prefix>00`, `<prefix>01`, ...
+    #[arg(long, required_unless_present = "manifest")]
+    prefix: Option<String>,
+    /// Number of hosts.
+    #[arg(long, required_unless_present = "manifest")]
+    number: Option<u32>,
+    /// First host number.
+    #[arg(long, default_value_t = 0)]
+    start: u32,
+    /// Count host numbers by this step.
+    #[arg(long, default_value_t = 1)]
+    step: u32,
+    /// Minimum number of digits of host numbers.
+    #[arg(long, default_value_t = 2)]
+    width: usize,
+    /// Host name suffix, e.g. `-dc1`.
+    #[arg(long, default_value = "")]
+    suffix: String,
+    /// Host numbers to skip, comma separated.
+    #[arg(long, value_delimiter = ',')]
+    exclude: Vec<u32>,
+    /// Home manager input, e.g. `home-23-05`.
+    #[arg(long)]
+    home_manager: Option<String>,
+    /// Nixpkgs input, e.g. `nixos-23-05`.
+    #[arg(long, required_unless_present = "manifest")]
+    nixpkgs: Option<String>,
+    #[arg(long, default_value = "x86_64-linux")]
+    system: String,
+    /// Root of the hive repository.
+    #[arg(long, default_value = ".")]
+    root: PathBuf,
+    /// Leave out the `{ cell, inputs, ... }:` argument pattern.
+    #[arg(long)]
+    scoped: bool,
+    /// Add an empty `# honey:begin <host>` region to the imports of every
+    /// host, for hand-written imports kept across runs. Regions already in
+    /// the files are kept without it.
+    #[arg(long)]
+    regions: bool,
+    /// Merge inherits from the same source into one line.
+    #[arg(long)]
+    merge_inherits: bool,
+    /// Also write the `flake.nix` growing the hive.
+    #[arg(long)]
+    flake: bool,
+    /// Fail on imports of cell block targets that exist neither below
+    /// `--root` nor among the generated files.
+    #[arg(long)]
+    validate: bool,
+    /// Print the files instead of writing them.
+    #[arg(long)]
+    dry_run: bool,
+    /// Write nothing, print a unified diff of every file on disk that
+    /// differs from the generated one and fail if any does. Regions are
+    /// compared as they are on disk.
+    #[arg(long, conflicts_with_all = ["dry_run", "force"])]
+    check: bool,
+    /// Overwrite existing files.
+    #[arg(long)]
+    force: bool,
+}
+
+impl Generate {
+    /// The fleet from the manifest, or from the command line.
+    fn manifest(&self) -> anyhow::Result<Manifest> {
+        let mut manifest = match &self.manifest {
+            Some(path) => Manifest::load(path)?,
+            None => Manifest {
+                cell: String::new(),
+                groups: vec![Group {
+                    prefix: self.prefix.clone().unwrap_or_default(),
+                    number: self.number.unwrap_or_default(),
+                    start: self.start,
+                    step: self.step,
+                    width: self.width,
+                    suffix: self.suffix.clone(),
+                    exclude: self.exclude.clone(),
+                    nixpkgs: self.nixpkgs.clone().unwrap_or_default(),
+                    home_manager: self.home_manager.clone(),
+                    system: self.system.clone(),
+                    imports: Vec::new(),
+                    deployment: None,
+                    disko: true,
+                }],
+                hosts: Vec::new(),
+                darwin_hosts: Vec::new(),
+                users: Vec::new(),
+                devshells: Vec::new(),
+                inputs: BTreeMap::new(),
+            },
+        };
+        if let Some(cell) = &self.cell {
+            manifest.cell = cell.clone();
+        }
+        manifest.validate()?;
+        Ok(manifest)
+    }
+}
+
+/// Report every dangling `cell.<block>.<target>` import of the fleet.
+fn validate(manifest: &Manifest, root: &Path, files: &[cell::File]) -> anyhow::Result<()> {
+    let mut tree = Tree::load(root).context("failed to read cells")?;
+    tree.overlay(root, files)?;
+    let mut dangling =
+        tree.check_configurations(&manifest.cell, &manifest.nixos_configurations()?.0);
+    dangling
+        .extend(tree.check_configurations(&manifest.cell, &manifest.darwin_configurations()?.0));
+    dangling
+        .extend(tree.check_home_configurations(&manifest.cell, &manifest.home_configurations()?));
+    if dangling.is_empty() {
+        return Ok(());
+    }
+    for dangling in &dangling {
+        eprintln!("{}", dangling);
+    }
+    anyhow::bail!("{} dangling imports", dangling.len());
+}
+
+fn generate(args: Generate) -> anyhow::Result<()> {
+    let manifest = args.manifest()?;
+    let hosts = manifest.hosts();
+    let names: Vec<String> = hosts.iter().map(|host| host.name.clone()).collect();
+    let disko_names: Vec<String> = hosts
+        .iter()
+        .filter(|host| host.disko)
+        .map(|host| host.name.clone())
+        .collect();
+    let home_configurations = manifest.home_configurations()?;
+
+    let base = match &args.manifest {
+        Some(path) => path.parent().unwrap_or(Path::new(".")),
+        None => Path::new("."),
+    };
+    let hardware_profiles = manifest
+        .hardware_profiles(base)
+        .context("failed to read hardware report")?;
+
+    let mut cell = Cell::new(&manifest.cell);
+    let directory = cell.path(&args.root);
+    let mut nixos_configurations = manifest.nixos_configurations()?;
+    let mut darwin_configurations = manifest.darwin_configurations()?;
+    for (block, configurations) in [
+        ("nixosConfigurations", &mut nixos_configurations.0),
+        ("darwinConfigurations", &mut darwin_configurations.0),
+    ] {
+        // Regions already on disk are kept without `--regions`.
+        let existing = region::names(&directory.join(format!("{}.nix", block)))?;
+        for configurations in configurations {
+            if args.regions || existing.contains(&configurations.name) {
+                let region = Region::new(&configurations.name);
+                configurations.configurations.push(region.into());
+            }
+        }
+    }
+
+    cell.block("nixosConfigurations", nixos_configurations);
+    for block in HOST_BLOCKS {
+        match &hardware_profiles {
+            Some(hardware_profiles) if block == "hardwareProfiles" => {
+                cell.block(block, hardware_profiles.clone())
+            }
+            _ if block == "diskoConfigurations" => {
+                cell.block(block, NixosConfigurations::stubs(&disko_names))
+            }
+            _ => cell.block(block, NixosConfigurations::stubs(&names)),
+        }
+    }
+    if let Some(colmena_configurations) = manifest.colmena_configurations() {
+        cell.block("colmenaConfigurations", colmena_configurations);
+    }
+    if !manifest.darwin_hosts.is_empty() {
+        let darwin_names: Vec<String> = manifest
+            .darwin_hosts
+            .iter()
+            .map(|host| host.name.clone())
+            .collect();
+        cell.block("darwinConfigurations", darwin_configurations);
+        for block in DARWIN_HOST_BLOCKS {
+            cell.block(block, NixosConfigurations::stubs(&darwin_names));
+        }
+    }
+    if !home_configurations.0.is_empty() {
+        cell.block("homeConfigurations", home_configurations);
+    }
+    if let Some(devshells) = manifest.devshells() {
+        cell.block("devshells", devshells);
+    }
+
+    if args.merge_inherits {
+        cell.merge_inherits();
+    }
+    let mut files = cell.files(&args.root, args.scoped)?;
+    if args.flake {
+        let cells = [cell];
+        files.push(Flake::new(&cells, &manifest.registry())?.file(&args.root)?);
+    }
+    region::preserve_files(&mut files)?;
+    if args.validate {
+        validate(&manifest, &args.root, &files)?;
+    }
+    if args.check {
+        let diffs = cell::diff(&files).context("failed to read existing files")?;
+        if diffs.is_empty() {
+            return Ok(());
+        }
+        for diff in &diffs {
+            print!("{}", diff);
+        }
+        anyhow::bail!("{} of {} files out of date", diffs.len(), files.len());
+    }
+    if args.dry_run {
+        for file in &files {
+            println!("==> {} <==", file.path.display());
+            print!("{}", file.contents);
+        }
+        return Ok(());
+    }
+    if let Err(error) = cell::write(&files, args.force) {
+        if error.kind() == io::ErrorKind::AlreadyExists {
+            anyhow::bail!("{}, pass --force to overwrite", error);
+        }
+        return Err(error).with_context(|| format!("failed to write cell `{}`", manifest.cell));
+    }
+    for file in &files {
+        println!("wrote {}", file.path.display());
+    }
+    Ok(())
+}
+
+fn main() -> anyhow::Result<()> {
+    match Cli::parse().command {
+        Command::Generate(args) => generate(args),
+    }
+}
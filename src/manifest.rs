@@ -0,0 +1,810 @@
+use crate::hive::hardware_profiles;
+use crate::hive::registry::{Entry, Registry};
+use crate::hive::*;
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Error returned when a manifest can not be loaded.
+#[derive(Debug)]
+pub enum Error {
+    Io(PathBuf, io::Error),
+    /// The file extension is not one of `toml`, `yaml`, `yml` or `json`.
+    UnknownFormat(PathBuf),
+    Toml(toml::de::Error),
+    Yaml(serde_yaml::Error),
+    Json(serde_json::Error),
+    /// The manifest deserialised, but describes an impossible fleet.
+    Invalid(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(path, error) => write!(f, "{}: {}", path.display(), error),
+            Error::UnknownFormat(path) => write!(
+                f,
+                "{}: unknown manifest format, expected .toml, .yaml, .yml or .json",
+                path.display()
+            ),
+            Error::Toml(error) => write!(f, "{}", error),
+            Error::Yaml(error) => write!(f, "{}", error),
+            Error::Json(error) => write!(f, "{}", error),
+            Error::Invalid(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(_, error) => Some(error),
+            Error::Toml(error) => Some(error),
+            Error::Yaml(error) => Some(error),
+            Error::Json(error) => Some(error),
+            Error::UnknownFormat(_) | Error::Invalid(_) => None,
+        }
+    }
+}
+
+/// `<owner> `<name>`: <error>`, as reported for a bee that does not validate.
+fn invalid_bee(owner: &str, name: &str, error: bee::Error) -> Error {
+    Error::Invalid(format!("{} `{}`: {}", owner, name, error))
+}
+
+fn default_system() -> String {
+    String::from("x86_64-linux")
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_step() -> u32 {
+    1
+}
+
+fn default_width() -> usize {
+    2
+}
+
+/// An extra import, either an attribute path rooted in a cell argument such as
+/// `cell.nixosProfiles.base`, or an attribute of a flake input.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(untagged, deny_unknown_fields)]
+pub enum ImportSpec {
+    Path(String),
+    Input { input: String, attribute: String },
+}
+
+impl ImportSpec {
+    fn input(&self) -> Option<&str> {
+        match self {
+            ImportSpec::Path(_) => None,
+            ImportSpec::Input { input, .. } => Some(input),
+        }
+    }
+}
+
+impl From<ImportSpec> for Import {
+    fn from(spec: ImportSpec) -> Self {
+        match spec {
+            ImportSpec::Path(path) => Import::new1(&path),
+            ImportSpec::Input { input, attribute } => Import::new("inputs", &input, &attribute),
+        }
+    }
+}
+
+/// Numbered hosts named `<prefix>00`, `<prefix>01`, ... like
+/// `NixosConfigurations::new1`, or as set by the naming fields, see
+/// `HostPattern`.
+///
+/// ```
+/// use honey::manifest::Manifest;
+///
+/// let manifest = Manifest::from_toml(r#"
+/// cell = "hosts"
+///
+/// [[groups]]
+/// prefix = "web"
+/// number = 3
+/// start = 1
+/// width = 3
+/// suffix = "-dc1"
+/// exclude = [2]
+/// nixpkgs = "nixos-23-05"
+/// "#)?;
+///
+/// let names: Vec<String> = manifest.hosts().into_iter().map(|host| host.name).collect();
+///
+/// assert_eq!(vec!["web001-dc1", "web003-dc1", "web004-dc1"], names);
+/// # Ok::<_, honey::manifest::Error>(())
+/// ```
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Group {
+    pub prefix: String,
+    pub number: u32,
+    #[serde(default)]
+    pub start: u32,
+    #[serde(default = "default_step")]
+    pub step: u32,
+    /// Minimum number of digits.
+    #[serde(default = "default_width")]
+    pub width: usize,
+    #[serde(default)]
+    pub suffix: String,
+    /// Numbers of retired hosts.
+    #[serde(default)]
+    pub exclude: Vec<u32>,
+    pub nixpkgs: String,
+    pub home_manager: Option<String>,
+    #[serde(default = "default_system")]
+    pub system: String,
+    #[serde(default)]
+    pub imports: Vec<ImportSpec>,
+    /// Colmena settings shared by every host of the group.
+    pub deployment: Option<Deployment>,
+    /// Import disko, off for diskless VMs.
+    #[serde(default = "default_true")]
+    pub disko: bool,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Host {
+    pub name: String,
+    pub nixpkgs: String,
+    pub home_manager: Option<String>,
+    #[serde(default = "default_system")]
+    pub system: String,
+    #[serde(default)]
+    pub imports: Vec<ImportSpec>,
+    pub deployment: Option<Deployment>,
+    /// Import disko, off for diskless VMs.
+    #[serde(default = "default_true")]
+    pub disko: bool,
+    /// nixos-facter report (`.json`) or `nixos-generate-config` output,
+    /// relative to the manifest.
+    pub hardware_report: Option<PathBuf>,
+}
+
+impl Group {
+    pub fn naming(&self) -> HostPattern {
+        HostPattern::new(&self.prefix, self.number)
+            .start(self.start)
+            .step(self.step)
+            .width(self.width)
+            .suffix(&self.suffix)
+            .exclude(self.exclude.iter().copied())
+    }
+}
+
+impl Host {
+    /// The configurations of the host. Unlike `NixosConfigurations::new1`,
+    /// a host with home-manager does not import
+    /// `cell.homeConfigurations.<host>`: the manifest writes
+    /// `homeConfigurations` entries only for its `users`, and home-manager
+    /// reaches the host through its bee.
+    ///
+    /// ```
+    /// use genco::prelude::*;
+    /// use honey::hive::*;
+    /// use honey::manifest::Manifest;
+    ///
+    /// let manifest = Manifest::from_yaml("
+    /// cell: hosts
+    /// hosts:
+    ///   - name: vm1
+    ///     nixpkgs: nixos-23-05
+    ///     disko: false
+    /// ")?;
+    ///
+    /// let imports = Imports::from(manifest.hosts[0].configurations()?);
+    ///
+    /// assert_eq!(4, imports.0.len());
+    ///
+    /// let manifest = Manifest::from_yaml("
+    /// cell: hosts
+    /// hosts:
+    ///   - name: machine00
+    ///     nixpkgs: nixos-23-05
+    ///     home-manager: home-23-05
+    /// ")?;
+    ///
+    /// let from_manifest = quote!($(NixosConfigurations(vec![manifest.hosts[0].configurations()?])));
+    /// let from_library = quote!($(NixosConfigurations::new1("machine", 1, Some("home-23-05"), "nixos-23-05", "x86_64-linux")?));
+    ///
+    /// let from_library: Vec<String> = from_library
+    ///     .to_file_vec()?
+    ///     .into_iter()
+    ///     .filter(|line| line.trim() != "cell.homeConfigurations.machine00")
+    ///     .collect();
+    ///
+    /// assert_eq!(from_library, from_manifest.to_file_vec()?);
+    /// # Ok::<_, Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn configurations(&self) -> Result<Configurations, bee::Error> {
+        let mut builder = Configurations::builder(&self.name)
+            .bee(Import::bee1(
+                &self.name,
+                self.home_manager.as_deref(),
+                &self.nixpkgs,
+                &self.system,
+            )?)
+            .disko(self.disko)
+            .home(false);
+        for import in &self.imports {
+            builder = builder.import(import.clone().into());
+        }
+        Ok(builder.build())
+    }
+}
+
+fn default_darwin_system() -> String {
+    String::from("aarch64-darwin")
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct DarwinHost {
+    pub name: String,
+    /// nix-darwin input, e.g. `darwin`.
+    pub darwin: String,
+    pub nixpkgs: String,
+    pub home_manager: Option<String>,
+    #[serde(default = "default_darwin_system")]
+    pub system: String,
+    #[serde(default)]
+    pub imports: Vec<ImportSpec>,
+}
+
+impl DarwinHost {
+    /// The configurations of the host, without a
+    /// `cell.homeConfigurations.<host>` import as for `Host::configurations`.
+    pub fn configurations(&self) -> Result<Configurations, bee::Error> {
+        let mut builder = ConfigurationsBuilder::darwin(&self.name)
+            .bee(Import::bee3(
+                &self.name,
+                &self.darwin,
+                self.home_manager.as_deref(),
+                &self.nixpkgs,
+                &self.system,
+            )?)
+            .home(false);
+        for import in &self.imports {
+            builder = builder.import(import.clone().into());
+        }
+        Ok(builder.build())
+    }
+}
+
+/// A standalone home-manager user, e.g. on a laptop without NixOS.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct User {
+    pub name: String,
+    pub nixpkgs: String,
+    pub home_manager: String,
+    #[serde(default = "default_system")]
+    pub system: String,
+    pub state_version: String,
+    /// Defaults to `/home/<name>`, or `/Users/<name>` on darwin.
+    pub home_directory: Option<String>,
+    #[serde(default)]
+    pub imports: Vec<ImportSpec>,
+}
+
+impl User {
+    pub fn home_configuration(&self) -> Result<HomeConfiguration, bee::Error> {
+        let mut home_configuration = HomeConfiguration::user(
+            &self.name,
+            &self.home_manager,
+            &self.nixpkgs,
+            &self.system,
+            &self.state_version,
+        )?;
+        if let (Some(home), Some(home_directory)) =
+            (&mut home_configuration.home, &self.home_directory)
+        {
+            home.home_directory = home_directory.clone();
+        }
+        for import in &self.imports {
+            home_configuration.imports.push(import.clone().into());
+        }
+        Ok(home_configuration)
+    }
+}
+
+/// A fleet description, for operators who would rather not write Rust.
+///
+/// ```
+/// use genco::prelude::*;
+/// use honey::manifest::Manifest;
+///
+/// let manifest = Manifest::from_toml(r#"
+/// cell = "hosts"
+///
+/// [[groups]]
+/// prefix = "machine"
+/// number = 1
+/// nixpkgs = "nixos-23-05"
+///
+/// [[hosts]]
+/// name = "builder"
+/// nixpkgs = "nixos-unstable"
+/// system = "aarch64-linux"
+/// imports = [
+///     "cell.nixosProfiles.builder",
+///     { input = "nixos-hardware", attribute = "nixosModules.raspberry-pi-4" },
+/// ]
+/// "#)?;
+///
+/// let nixos_configurations = manifest.nixos_configurations()?;
+///
+/// let toks = quote!($nixos_configurations);
+///
+/// assert_eq!(
+///     vec![
+///         "{",
+///         "    cell,",
+///         "    inputs,",
+///         "    ...",
+///         "}:",
+///         "",
+///         "let",
+///         "    inherit (inputs) disko;",
+///         "    inherit (inputs) nixos-23-05;",
+///         "    inherit (inputs) nixos-hardware;",
+///         "    inherit (inputs) nixos-unstable;",
+///         "    bee-builder = {",
+///         "        bee = {",
+///         "            pkgs = nixos-unstable.legacyPackages;",
+///         "            system = \"aarch64-linux\";",
+///         "        };",
+///         "    };",
+///         "    bee-machine00 = {",
+///         "        bee = {",
+///         "            pkgs = nixos-23-05.legacyPackages;",
+///         "            system = \"x86_64-linux\";",
+///         "        };",
+///         "    };",
+///         "in",
+///         "",
+///         "{",
+///         "    machine00 = {",
+///         "        imports = [",
+///         "            bee-machine00",
+///         "            cell.diskoConfigurations.machine00",
+///         "            cell.hardwareProfiles.machine00",
+///         "            cell.nixosModules.machine00",
+///         "            cell.nixosProfiles.machine00",
+///         "            disko.nixosModules.disko",
+///         "        ];",
+///         "    };",
+///         "    builder = {",
+///         "        imports = [",
+///         "            bee-builder",
+///         "            cell.diskoConfigurations.builder",
+///         "            cell.hardwareProfiles.builder",
+///         "            cell.nixosModules.builder",
+///         "            cell.nixosProfiles.builder",
+///         "            disko.nixosModules.disko",
+///         "            nixos-hardware.nixosModules.raspberry-pi-4",
+///         "        ];",
+///         "    };",
+///         "}",
+///     ],
+///     toks.to_file_vec()?
+/// );
+/// # Ok::<_, Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Manifest {
+    /// Cell the blocks are written to.
+    pub cell: String,
+    #[serde(default)]
+    pub groups: Vec<Group>,
+    #[serde(default)]
+    pub hosts: Vec<Host>,
+    #[serde(default)]
+    pub darwin_hosts: Vec<DarwinHost>,
+    #[serde(default)]
+    pub users: Vec<User>,
+    /// Shells of the `devshells` block.
+    #[serde(default)]
+    pub devshells: Vec<Devshell>,
+    /// Inputs besides the ones honey knows, or overriding them.
+    #[serde(default)]
+    pub inputs: BTreeMap<String, Entry>,
+}
+
+impl Manifest {
+    pub fn from_toml(input: &str) -> Result<Self, Error> {
+        let manifest: Self = toml::from_str(input).map_err(Error::Toml)?;
+        manifest.validate()?;
+        Ok(manifest)
+    }
+
+    /// ```
+    /// use honey::manifest::Manifest;
+    ///
+    /// let manifest = Manifest::from_yaml("
+    /// cell: hosts
+    /// groups:
+    ///   - prefix: machine
+    ///     number: 2
+    ///     nixpkgs: nixos-23-05
+    ///     home-manager: home-23-05
+    /// ")?;
+    ///
+    /// let names: Vec<String> = manifest.hosts().into_iter().map(|host| host.name).collect();
+    ///
+    /// assert_eq!(vec!["machine00", "machine01"], names);
+    /// # Ok::<_, honey::manifest::Error>(())
+    /// ```
+    pub fn from_yaml(input: &str) -> Result<Self, Error> {
+        let manifest: Self = serde_yaml::from_str(input).map_err(Error::Yaml)?;
+        manifest.validate()?;
+        Ok(manifest)
+    }
+
+    /// ```
+    /// use honey::manifest::Manifest;
+    ///
+    /// let error = Manifest::from_json(r#"{
+    ///     "cell": "hosts",
+    ///     "groups": [{ "prefix": "machine", "number": 2, "nixpkgs": "nixos-23-05" }],
+    ///     "hosts": [{ "name": "machine01", "nixpkgs": "nixos-23-05" }]
+    /// }"#).unwrap_err();
+    ///
+    /// assert_eq!("duplicate host `machine01`", error.to_string());
+    ///
+    /// let error = Manifest::from_json(r#"{
+    ///     "cell": "hosts",
+    ///     "darwin-hosts": [{ "name": "macbook", "darwin": "darwin", "nixpkgs": "nixpkgs-darwin", "system": "x86_64-linux" }]
+    /// }"#).unwrap_err();
+    ///
+    /// assert_eq!("host `macbook`: `bee.darwin` does not fit system `x86_64-linux`", error.to_string());
+    /// ```
+    pub fn from_json(input: &str) -> Result<Self, Error> {
+        let manifest: Self = serde_json::from_str(input).map_err(Error::Json)?;
+        manifest.validate()?;
+        Ok(manifest)
+    }
+
+    /// Load a manifest, picking the format from the file extension.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let format = path.extension().and_then(|extension| extension.to_str());
+        let parse = match format {
+            Some("toml") => Self::from_toml,
+            Some("yaml" | "yml") => Self::from_yaml,
+            Some("json") => Self::from_json,
+            _ => return Err(Error::UnknownFormat(path.to_path_buf())),
+        };
+        let input =
+            std::fs::read_to_string(path).map_err(|error| Error::Io(path.to_path_buf(), error))?;
+        parse(&input)
+    }
+
+    /// Every host of the fleet, groups first.
+    pub fn hosts(&self) -> Vec<Host> {
+        let mut hosts = Vec::new();
+        for group in &self.groups {
+            for name in group.naming().names() {
+                hosts.push(Host {
+                    name,
+                    nixpkgs: group.nixpkgs.clone(),
+                    home_manager: group.home_manager.clone(),
+                    system: group.system.clone(),
+                    imports: group.imports.clone(),
+                    deployment: group.deployment.clone(),
+                    disko: group.disko,
+                    hardware_report: None,
+                });
+            }
+        }
+        hosts.extend(self.hosts.iter().cloned());
+        hosts
+    }
+
+    /// ```
+    /// use honey::manifest::Manifest;
+    ///
+    /// let manifest = Manifest::from_yaml("
+    /// cell: hosts
+    /// darwin-hosts:
+    ///   - name: macbook
+    ///     darwin: darwin
+    ///     nixpkgs: nixpkgs-darwin
+    /// ")?;
+    ///
+    /// let darwin_configurations = manifest.darwin_configurations()?;
+    ///
+    /// assert_eq!("macbook", darwin_configurations.0[0].name);
+    /// # Ok::<_, honey::manifest::Error>(())
+    /// ```
+    pub fn darwin_configurations(&self) -> Result<DarwinConfigurations, Error> {
+        let configurations = self
+            .darwin_hosts
+            .iter()
+            .map(|host| {
+                host.configurations()
+                    .map_err(|error| invalid_bee("host", &host.name, error))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(DarwinConfigurations(configurations))
+    }
+
+    pub fn nixos_configurations(&self) -> Result<NixosConfigurations, Error> {
+        let configurations = self
+            .hosts()
+            .iter()
+            .map(|host| {
+                host.configurations()
+                    .map_err(|error| invalid_bee("host", &host.name, error))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(NixosConfigurations(configurations))
+    }
+
+    /// The `colmenaConfigurations` block, `None` unless a group or host has a
+    /// `deployment` table.
+    ///
+    /// ```
+    /// use honey::manifest::Manifest;
+    ///
+    /// let manifest = Manifest::from_toml(r#"
+    /// cell = "hosts"
+    ///
+    /// [[groups]]
+    /// prefix = "web"
+    /// number = 2
+    /// nixpkgs = "nixos-23-05"
+    /// deployment = { target-user = "deploy", tags = ["web"] }
+    ///
+    /// [[hosts]]
+    /// name = "builder"
+    /// nixpkgs = "nixos-23-05"
+    /// "#)?;
+    ///
+    /// let colmena_configurations = manifest.colmena_configurations().unwrap();
+    ///
+    /// let names: Vec<&str> = colmena_configurations.0.iter().map(|host| host.name.as_str()).collect();
+    ///
+    /// assert_eq!(vec!["web00", "web01", "builder"], names);
+    /// assert_eq!(Some(String::from("deploy")), colmena_configurations.0[1].deployment.target_user);
+    /// assert!(colmena_configurations.0[2].deployment.is_empty());
+    /// # Ok::<_, honey::manifest::Error>(())
+    /// ```
+    pub fn colmena_configurations(&self) -> Option<ColmenaConfigurations> {
+        let hosts = self.hosts();
+        if hosts.iter().all(|host| host.deployment.is_none()) {
+            return None;
+        }
+        let names: Vec<String> = hosts.iter().map(|host| host.name.clone()).collect();
+        let mut deployments = hosts
+            .into_iter()
+            .map(|host| host.deployment.unwrap_or_default());
+        Some(ColmenaConfigurations::new(
+            &NixosConfigurations::stubs(&names),
+            |_| deployments.next().unwrap_or_default(),
+        ))
+    }
+
+    /// The `devshells` block, `None` without shells.
+    ///
+    /// ```
+    /// use honey::manifest::Manifest;
+    ///
+    /// let manifest = Manifest::from_toml(r#"
+    /// cell = "hosts"
+    ///
+    /// [[devshells]]
+    /// name = "default"
+    /// packages = ["colmena"]
+    /// commands = [{ name = "deploy", help = "Deploy every host", command = "colmena apply" }]
+    /// env = { NIX_CONFIG = "experimental-features = nix-command flakes" }
+    /// "#)?;
+    ///
+    /// let devshells = manifest.devshells().unwrap();
+    ///
+    /// assert_eq!(vec![String::from("colmena")], devshells.0[0].packages);
+    /// assert_eq!(Some(String::from("colmena apply")), devshells.0[0].commands[0].command);
+    /// # Ok::<_, honey::manifest::Error>(())
+    /// ```
+    pub fn devshells(&self) -> Option<Devshells> {
+        if self.devshells.is_empty() {
+            return None;
+        }
+        Some(Devshells(self.devshells.clone()))
+    }
+
+    /// The `hardwareProfiles` block, `None` unless a host has a
+    /// `hardware-report`. Reports are looked up relative to `base`, the
+    /// directory of the manifest; hosts without one get an empty entry.
+    pub fn hardware_profiles(
+        &self,
+        base: &Path,
+    ) -> Result<Option<HardwareProfiles>, hardware_profiles::Error> {
+        let hosts = self.hosts();
+        if hosts.iter().all(|host| host.hardware_report.is_none()) {
+            return Ok(None);
+        }
+        let mut hardware_profiles = Vec::new();
+        for host in hosts {
+            hardware_profiles.push(match &host.hardware_report {
+                Some(report) => HardwareProfile::load(&host.name, &base.join(report))?,
+                None => HardwareProfile::new(&host.name),
+            });
+        }
+        Ok(Some(HardwareProfiles(hardware_profiles)))
+    }
+
+    /// The `homeConfigurations` block, an entry for every standalone user.
+    /// Hosts with home-manager get it through their bee and import no entry.
+    ///
+    /// ```
+    /// use honey::manifest::Manifest;
+    ///
+    /// let manifest = Manifest::from_toml(r#"
+    /// cell = "hosts"
+    ///
+    /// [[hosts]]
+    /// name = "desktop"
+    /// nixpkgs = "nixos-23-05"
+    /// home-manager = "home-23-05"
+    ///
+    /// [[users]]
+    /// name = "alice"
+    /// nixpkgs = "nixpkgs-darwin"
+    /// home-manager = "home-23-05"
+    /// system = "aarch64-darwin"
+    /// state-version = "23.05"
+    /// "#)?;
+    ///
+    /// let names: Vec<String> = manifest.home_configurations()?.0.into_iter().map(|home| home.name).collect();
+    ///
+    /// assert_eq!(vec!["alice"], names);
+    /// # Ok::<_, honey::manifest::Error>(())
+    /// ```
+    pub fn home_configurations(&self) -> Result<HomeConfigurations, Error> {
+        let home_configurations = self
+            .users
+            .iter()
+            .map(|user| {
+                user.home_configuration()
+                    .map_err(|error| invalid_bee("user", &user.name, error))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(HomeConfigurations(home_configurations))
+    }
+
+    /// The default registry extended with the `inputs` table.
+    ///
+    /// ```
+    /// use honey::manifest::Manifest;
+    ///
+    /// let manifest = Manifest::from_toml(r#"
+    /// cell = "hosts"
+    ///
+    /// [[hosts]]
+    /// name = "laptop"
+    /// nixpkgs = "nixos-23-05"
+    /// imports = [{ input = "lanzaboote", attribute = "nixosModules.lanzaboote" }]
+    ///
+    /// [inputs.lanzaboote]
+    /// url = "github:nix-community/lanzaboote"
+    /// follows = { nixpkgs = "nixos-23-05" }
+    /// "#)?;
+    ///
+    /// assert!(manifest.registry().resolve("lanzaboote").is_ok());
+    ///
+    /// let error = Manifest::from_toml(r#"
+    /// cell = "hosts"
+    ///
+    /// [[hosts]]
+    /// name = "laptop"
+    /// nixpkgs = "nixos-2305"
+    /// "#).unwrap_err();
+    ///
+    /// assert_eq!(
+    ///     "host `laptop`: unknown input `nixos-2305`, did you mean `nixos-23-05`?",
+    ///     error.to_string()
+    /// );
+    /// # Ok::<_, honey::manifest::Error>(())
+    /// ```
+    pub fn registry(&self) -> Registry {
+        let mut registry = Registry::default();
+        for (name, entry) in &self.inputs {
+            registry.insert(name, entry.clone());
+        }
+        registry
+    }
+
+    /// Input names each host and user refers to.
+    fn inputs(&self) -> Vec<(String, Vec<&str>)> {
+        let mut inputs = Vec::new();
+        for group in &self.groups {
+            let mut names = vec![group.nixpkgs.as_str()];
+            names.extend(group.home_manager.as_deref());
+            names.extend(group.imports.iter().filter_map(ImportSpec::input));
+            inputs.push((format!("group `{}`", group.prefix), names));
+        }
+        for host in &self.hosts {
+            let mut names = vec![host.nixpkgs.as_str()];
+            names.extend(host.home_manager.as_deref());
+            names.extend(host.imports.iter().filter_map(ImportSpec::input));
+            inputs.push((format!("host `{}`", host.name), names));
+        }
+        for host in &self.darwin_hosts {
+            let mut names = vec![host.darwin.as_str(), host.nixpkgs.as_str()];
+            names.extend(host.home_manager.as_deref());
+            names.extend(host.imports.iter().filter_map(ImportSpec::input));
+            inputs.push((format!("host `{}`", host.name), names));
+        }
+        for user in &self.users {
+            let mut names = vec![user.nixpkgs.as_str(), user.home_manager.as_str()];
+            names.extend(user.imports.iter().filter_map(ImportSpec::input));
+            inputs.push((format!("user `{}`", user.name), names));
+        }
+        inputs
+    }
+
+    /// Check a manifest built or changed in code the way `load` checks one
+    /// read from a file: inputs must be known to the registry, bees must
+    /// validate and names must be unique.
+    ///
+    /// ```
+    /// use honey::manifest::Manifest;
+    ///
+    /// let mut manifest = Manifest::from_toml(r#"
+    /// cell = "hosts"
+    ///
+    /// [[hosts]]
+    /// name = "machine00"
+    /// nixpkgs = "nixos-23-05"
+    /// "#)?;
+    ///
+    /// manifest.hosts[0].nixpkgs = String::from("nixos-2305");
+    ///
+    /// assert!(manifest.validate().is_err());
+    /// # Ok::<_, honey::manifest::Error>(())
+    /// ```
+    pub fn validate(&self) -> Result<(), Error> {
+        let registry = self.registry();
+        for (owner, names) in self.inputs() {
+            for name in names {
+                if let Err(error) = registry.check(name) {
+                    return Err(Error::Invalid(format!("{}: {}", owner, error)));
+                }
+            }
+        }
+        self.nixos_configurations()?;
+        self.darwin_configurations()?;
+        self.home_configurations()?;
+        let mut names = BTreeSet::new();
+        for host in self.hosts() {
+            if !names.insert(host.name.clone()) {
+                return Err(Error::Invalid(format!("duplicate host `{}`", host.name)));
+            }
+        }
+        for host in &self.darwin_hosts {
+            if !names.insert(host.name.clone()) {
+                return Err(Error::Invalid(format!("duplicate host `{}`", host.name)));
+            }
+        }
+        for user in &self.users {
+            if !names.insert(user.name.clone()) {
+                return Err(Error::Invalid(format!(
+                    "user `{}` clashes with another host or user",
+                    user.name
+                )));
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,18 @@
+pub mod expr;
+mod lexer;
+pub mod parser;
+pub mod ser;
+pub mod value;
+
+pub use crate::nix::expr::AttrName;
+pub use crate::nix::expr::BinaryOp;
+pub use crate::nix::expr::Binding;
+pub use crate::nix::expr::Expr;
+pub use crate::nix::expr::Formal;
+pub use crate::nix::expr::Param;
+pub use crate::nix::expr::StrPart;
+pub use crate::nix::expr::UnaryOp;
+pub use crate::nix::parser::parse;
+pub use crate::nix::parser::Error;
+pub use crate::nix::ser::to_tokens;
+pub use crate::nix::value::NixValue;
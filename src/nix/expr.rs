@@ -0,0 +1,411 @@
+use std::fmt;
+
+/// A parsed Nix expression.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Expr {
+    Ident(String),
+    Int(i64),
+    Float(f64),
+    String(Vec<StrPart>),
+    Path(String),
+    /// A search path such as `<nixpkgs>`, stored without the angle brackets.
+    SearchPath(String),
+    List(Vec<Expr>),
+    AttrSet {
+        recursive: bool,
+        bindings: Vec<Binding>,
+    },
+    Select {
+        expr: Box<Expr>,
+        path: Vec<AttrName>,
+        default: Option<Box<Expr>>,
+    },
+    HasAttr {
+        expr: Box<Expr>,
+        path: Vec<AttrName>,
+    },
+    Apply {
+        function: Box<Expr>,
+        argument: Box<Expr>,
+    },
+    Lambda {
+        param: Param,
+        body: Box<Expr>,
+    },
+    Let {
+        bindings: Vec<Binding>,
+        body: Box<Expr>,
+    },
+    With {
+        namespace: Box<Expr>,
+        body: Box<Expr>,
+    },
+    If {
+        condition: Box<Expr>,
+        consequent: Box<Expr>,
+        alternative: Box<Expr>,
+    },
+    Assert {
+        condition: Box<Expr>,
+        body: Box<Expr>,
+    },
+    Unary {
+        op: UnaryOp,
+        expr: Box<Expr>,
+    },
+    Binary {
+        op: BinaryOp,
+        lhs: Box<Expr>,
+        rhs: Box<Expr>,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum StrPart {
+    Literal(String),
+    Interpolation(Expr),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum AttrName {
+    Ident(String),
+    String(Vec<StrPart>),
+    Interpolation(Expr),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Binding {
+    /// `a.b.c = value;`
+    Attr { path: Vec<AttrName>, value: Expr },
+    /// `inherit (from) a b;`
+    Inherit {
+        from: Option<Expr>,
+        names: Vec<AttrName>,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Param {
+    /// `x: ...`
+    Ident(String),
+    /// `{ a, b ? 1, ... } @ args: ...`
+    Pattern {
+        formals: Vec<Formal>,
+        ellipsis: bool,
+        bind: Option<String>,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Formal {
+    pub name: String,
+    pub default: Option<Expr>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnaryOp {
+    Negate,
+    Not,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BinaryOp {
+    Concat,
+    Mul,
+    Div,
+    Add,
+    Sub,
+    Update,
+    Less,
+    LessEq,
+    Greater,
+    GreaterEq,
+    Eq,
+    NotEq,
+    And,
+    Or,
+    Implication,
+}
+
+impl BinaryOp {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            BinaryOp::Concat => "++",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Update => "//",
+            BinaryOp::Less => "<",
+            BinaryOp::LessEq => "<=",
+            BinaryOp::Greater => ">",
+            BinaryOp::GreaterEq => ">=",
+            BinaryOp::Eq => "==",
+            BinaryOp::NotEq => "!=",
+            BinaryOp::And => "&&",
+            BinaryOp::Or => "||",
+            BinaryOp::Implication => "->",
+        }
+    }
+}
+
+impl Expr {
+    /// The identifiers of a plain attribute path such as `cell.nixosModules.x`,
+    /// or `None` when the expression is anything else.
+    ///
+    /// ```
+    /// use honey::nix;
+    ///
+    /// let expr = nix::parse("cell.hardwareProfiles.machine1")?;
+    /// assert_eq!(
+    ///     Some(vec!["cell", "hardwareProfiles", "machine1"]),
+    ///     expr.attr_path()
+    /// );
+    ///
+    /// let expr = nix::parse("f x")?;
+    /// assert_eq!(None, expr.attr_path());
+    /// # Ok::<_, nix::Error>(())
+    /// ```
+    pub fn attr_path(&self) -> Option<Vec<&str>> {
+        match self {
+            Expr::Ident(name) => Some(vec![name.as_str()]),
+            Expr::Select {
+                expr,
+                path,
+                default: None,
+            } => {
+                let mut names = expr.attr_path()?;
+                for name in path {
+                    names.push(name.as_str()?);
+                }
+                Some(names)
+            }
+            _ => None,
+        }
+    }
+
+    fn is_atom(&self) -> bool {
+        matches!(
+            self,
+            Expr::Ident(_)
+                | Expr::Int(_)
+                | Expr::Float(_)
+                | Expr::String(_)
+                | Expr::Path(_)
+                | Expr::SearchPath(_)
+                | Expr::List(_)
+                | Expr::AttrSet { .. }
+                | Expr::Select { default: None, .. }
+        )
+    }
+}
+
+impl AttrName {
+    /// The name as a plain string, when it holds no interpolation.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            AttrName::Ident(name) => Some(name),
+            AttrName::String(parts) => match parts.as_slice() {
+                [] => Some(""),
+                [StrPart::Literal(literal)] => Some(literal),
+                _ => None,
+            },
+            AttrName::Interpolation(_) => None,
+        }
+    }
+}
+
+struct Atom<'a>(&'a Expr);
+
+impl fmt::Display for Atom<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.is_atom() {
+            write!(f, "{}", self.0)
+        } else {
+            write!(f, "({})", self.0)
+        }
+    }
+}
+
+fn write_string_parts(f: &mut fmt::Formatter<'_>, parts: &[StrPart]) -> fmt::Result {
+    f.write_str("\"")?;
+    for part in parts {
+        match part {
+            StrPart::Literal(literal) => {
+                let mut chars = literal.chars().peekable();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => f.write_str("\\\"")?,
+                        '\\' => f.write_str("\\\\")?,
+                        '\n' => f.write_str("\\n")?,
+                        '\r' => f.write_str("\\r")?,
+                        '\t' => f.write_str("\\t")?,
+                        '$' if chars.peek() == Some(&'{') => f.write_str("\\$")?,
+                        c => write!(f, "{}", c)?,
+                    }
+                }
+            }
+            StrPart::Interpolation(expr) => write!(f, "${{{}}}", expr)?,
+        }
+    }
+    f.write_str("\"")
+}
+
+fn write_attr_path(f: &mut fmt::Formatter<'_>, path: &[AttrName]) -> fmt::Result {
+    for (index, name) in path.iter().enumerate() {
+        if index > 0 {
+            f.write_str(".")?;
+        }
+        write!(f, "{}", name)?;
+    }
+    Ok(())
+}
+
+fn write_bindings(f: &mut fmt::Formatter<'_>, bindings: &[Binding]) -> fmt::Result {
+    for binding in bindings {
+        write!(f, " {}", binding)?;
+    }
+    Ok(())
+}
+
+impl fmt::Display for AttrName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AttrName::Ident(name) => f.write_str(name),
+            AttrName::String(parts) => write_string_parts(f, parts),
+            AttrName::Interpolation(expr) => write!(f, "${{{}}}", expr),
+        }
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Binding::Attr { path, value } => {
+                write_attr_path(f, path)?;
+                write!(f, " = {};", value)
+            }
+            Binding::Inherit { from, names } => {
+                f.write_str("inherit")?;
+                if let Some(from) = from {
+                    write!(f, " ({})", from)?;
+                }
+                for name in names {
+                    write!(f, " {}", name)?;
+                }
+                f.write_str(";")
+            }
+        }
+    }
+}
+
+impl fmt::Display for Param {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Param::Ident(name) => f.write_str(name),
+            Param::Pattern {
+                formals,
+                ellipsis,
+                bind,
+            } => {
+                f.write_str("{")?;
+                let mut first = true;
+                for formal in formals {
+                    f.write_str(if first { " " } else { ", " })?;
+                    first = false;
+                    f.write_str(&formal.name)?;
+                    if let Some(default) = &formal.default {
+                        write!(f, " ? {}", default)?;
+                    }
+                }
+                if *ellipsis {
+                    f.write_str(if first { " ..." } else { ", ..." })?;
+                }
+                f.write_str(" }")?;
+                if let Some(bind) = bind {
+                    write!(f, " @ {}", bind)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Renders the expression on a single line, mainly for error messages.
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Ident(name) => f.write_str(name),
+            Expr::Int(value) => write!(f, "{}", value),
+            Expr::Float(value) => write!(f, "{:?}", value),
+            Expr::String(parts) => write_string_parts(f, parts),
+            Expr::Path(path) => f.write_str(path),
+            Expr::SearchPath(path) => write!(f, "<{}>", path),
+            Expr::List(items) => {
+                f.write_str("[")?;
+                for item in items {
+                    write!(f, " {}", Atom(item))?;
+                }
+                f.write_str(" ]")
+            }
+            Expr::AttrSet {
+                recursive,
+                bindings,
+            } => {
+                if *recursive {
+                    f.write_str("rec ")?;
+                }
+                f.write_str("{")?;
+                write_bindings(f, bindings)?;
+                f.write_str(" }")
+            }
+            Expr::Select {
+                expr,
+                path,
+                default,
+            } => {
+                write!(f, "{}.", Atom(expr))?;
+                write_attr_path(f, path)?;
+                if let Some(default) = default {
+                    write!(f, " or {}", Atom(default))?;
+                }
+                Ok(())
+            }
+            Expr::HasAttr { expr, path } => {
+                write!(f, "{} ? ", Atom(expr))?;
+                write_attr_path(f, path)
+            }
+            Expr::Apply { function, argument } => {
+                if let Expr::Apply { .. } = **function {
+                    write!(f, "{} {}", function, Atom(argument))
+                } else {
+                    write!(f, "{} {}", Atom(function), Atom(argument))
+                }
+            }
+            Expr::Lambda { param, body } => write!(f, "{}: {}", param, body),
+            Expr::Let { bindings, body } => {
+                f.write_str("let")?;
+                write_bindings(f, bindings)?;
+                write!(f, " in {}", body)
+            }
+            Expr::With { namespace, body } => write!(f, "with {}; {}", namespace, body),
+            Expr::If {
+                condition,
+                consequent,
+                alternative,
+            } => write!(
+                f,
+                "if {} then {} else {}",
+                condition, consequent, alternative
+            ),
+            Expr::Assert { condition, body } => write!(f, "assert {}; {}", condition, body),
+            Expr::Unary { op, expr } => match op {
+                UnaryOp::Negate => write!(f, "-{}", Atom(expr)),
+                UnaryOp::Not => write!(f, "!{}", Atom(expr)),
+            },
+            Expr::Binary { op, lhs, rhs } => {
+                write!(f, "{} {} {}", Atom(lhs), op.symbol(), Atom(rhs))
+            }
+        }
+    }
+}
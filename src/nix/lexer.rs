@@ -0,0 +1,601 @@
+use crate::nix::parser::Error;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Token {
+    Ident(String),
+    Int(i64),
+    Float(f64),
+    Path(String),
+    SearchPath(String),
+    Str(Vec<StrToken>),
+    Let,
+    In,
+    Rec,
+    With,
+    Inherit,
+    If,
+    Then,
+    Else,
+    Assert,
+    LBrace,
+    RBrace,
+    LBracket,
+    RBracket,
+    LParen,
+    RParen,
+    Semicolon,
+    Colon,
+    Comma,
+    Dot,
+    Ellipsis,
+    Assign,
+    At,
+    Question,
+    Not,
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Concat,
+    Update,
+    Eq,
+    NotEq,
+    Less,
+    LessEq,
+    Greater,
+    GreaterEq,
+    And,
+    Or,
+    Implication,
+    Interpolate(Vec<Spanned>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum StrToken {
+    Literal(String),
+    Interpolation(Vec<Spanned>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Spanned {
+    pub token: Token,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Token {
+    pub fn describe(&self) -> String {
+        match self {
+            Token::Ident(name) => format!("identifier `{}`", name),
+            Token::Int(value) => format!("integer `{}`", value),
+            Token::Float(value) => format!("float `{}`", value),
+            Token::Path(path) | Token::SearchPath(path) => format!("path `{}`", path),
+            Token::Str(_) => String::from("string"),
+            Token::Interpolate(_) => String::from("`${`"),
+            token => format!("`{}`", token.symbol()),
+        }
+    }
+
+    fn symbol(&self) -> &'static str {
+        match self {
+            Token::Let => "let",
+            Token::In => "in",
+            Token::Rec => "rec",
+            Token::With => "with",
+            Token::Inherit => "inherit",
+            Token::If => "if",
+            Token::Then => "then",
+            Token::Else => "else",
+            Token::Assert => "assert",
+            Token::LBrace => "{",
+            Token::RBrace => "}",
+            Token::LBracket => "[",
+            Token::RBracket => "]",
+            Token::LParen => "(",
+            Token::RParen => ")",
+            Token::Semicolon => ";",
+            Token::Colon => ":",
+            Token::Comma => ",",
+            Token::Dot => ".",
+            Token::Ellipsis => "...",
+            Token::Assign => "=",
+            Token::At => "@",
+            Token::Question => "?",
+            Token::Not => "!",
+            Token::Plus => "+",
+            Token::Minus => "-",
+            Token::Star => "*",
+            Token::Slash => "/",
+            Token::Concat => "++",
+            Token::Update => "//",
+            Token::Eq => "==",
+            Token::NotEq => "!=",
+            Token::Less => "<",
+            Token::LessEq => "<=",
+            Token::Greater => ">",
+            Token::GreaterEq => ">=",
+            Token::And => "&&",
+            Token::Or => "||",
+            Token::Implication => "->",
+            _ => "",
+        }
+    }
+}
+
+/// Split Nix source into tokens. String interpolations are lexed recursively,
+/// so `${ ... }` becomes a nested token list.
+pub fn tokenize(input: &str) -> Result<Vec<Spanned>, Error> {
+    let mut lexer = Lexer {
+        chars: input.chars().collect(),
+        pos: 0,
+        line: 1,
+        column: 1,
+    };
+    let tokens = lexer.tokens(false)?;
+    Ok(tokens)
+}
+
+struct Lexer {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+    column: usize,
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '\'' || c == '-'
+}
+
+fn is_path_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-' | '+')
+}
+
+impl Lexer {
+    fn peek(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.chars.get(self.pos).copied()?;
+        self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn error(&self, message: String) -> Error {
+        Error {
+            line: self.line,
+            column: self.column,
+            message,
+        }
+    }
+
+    /// Lex until end of input, or until the `}` closing an interpolation when
+    /// `nested` is set.
+    fn tokens(&mut self, nested: bool) -> Result<Vec<Spanned>, Error> {
+        let mut tokens = Vec::new();
+        let mut depth = 0usize;
+        loop {
+            self.skip_trivia()?;
+            let (line, column) = (self.line, self.column);
+            let c = match self.peek(0) {
+                Some(c) => c,
+                None if nested => return Err(self.error(String::from("unterminated `${`"))),
+                None => return Ok(tokens),
+            };
+            if nested && c == '}' && depth == 0 {
+                self.bump();
+                return Ok(tokens);
+            }
+            let token = self.token(c)?;
+            match token {
+                Token::LBrace => depth += 1,
+                Token::RBrace => depth = depth.saturating_sub(1),
+                _ => (),
+            }
+            tokens.push(Spanned {
+                token,
+                line,
+                column,
+            });
+        }
+    }
+
+    fn skip_trivia(&mut self) -> Result<(), Error> {
+        loop {
+            match (self.peek(0), self.peek(1)) {
+                (Some(c), _) if c.is_whitespace() => {
+                    self.bump();
+                }
+                (Some('#'), _) => {
+                    while let Some(c) = self.bump() {
+                        if c == '\n' {
+                            break;
+                        }
+                    }
+                }
+                (Some('/'), Some('*')) => {
+                    self.bump();
+                    self.bump();
+                    loop {
+                        match (self.peek(0), self.peek(1)) {
+                            (Some('*'), Some('/')) => {
+                                self.bump();
+                                self.bump();
+                                break;
+                            }
+                            (Some(_), _) => {
+                                self.bump();
+                            }
+                            (None, _) => {
+                                return Err(self.error(String::from("unterminated comment")))
+                            }
+                        }
+                    }
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    fn token(&mut self, c: char) -> Result<Token, Error> {
+        if let Some(path) = self.path() {
+            return Ok(Token::Path(path));
+        }
+        if c == '<' {
+            if let Some(path) = self.search_path() {
+                return Ok(Token::SearchPath(path));
+            }
+        }
+        if is_ident_start(c) {
+            return Ok(self.ident());
+        }
+        if c.is_ascii_digit() {
+            return self.number();
+        }
+        if c == '"' {
+            self.bump();
+            return self.string();
+        }
+        if c == '\'' && self.peek(1) == Some('\'') {
+            self.bump();
+            self.bump();
+            return self.indented_string();
+        }
+        if c == '$' && self.peek(1) == Some('{') {
+            self.bump();
+            self.bump();
+            return Ok(Token::Interpolate(self.tokens(true)?));
+        }
+        let two = (c, self.peek(1).unwrap_or('\0'));
+        let (token, len) = match two {
+            ('.', '.') if self.peek(2) == Some('.') => (Token::Ellipsis, 3),
+            ('+', '+') => (Token::Concat, 2),
+            ('/', '/') => (Token::Update, 2),
+            ('=', '=') => (Token::Eq, 2),
+            ('!', '=') => (Token::NotEq, 2),
+            ('<', '=') => (Token::LessEq, 2),
+            ('>', '=') => (Token::GreaterEq, 2),
+            ('&', '&') => (Token::And, 2),
+            ('|', '|') => (Token::Or, 2),
+            ('-', '>') => (Token::Implication, 2),
+            ('{', _) => (Token::LBrace, 1),
+            ('}', _) => (Token::RBrace, 1),
+            ('[', _) => (Token::LBracket, 1),
+            (']', _) => (Token::RBracket, 1),
+            ('(', _) => (Token::LParen, 1),
+            (')', _) => (Token::RParen, 1),
+            (';', _) => (Token::Semicolon, 1),
+            (':', _) => (Token::Colon, 1),
+            (',', _) => (Token::Comma, 1),
+            ('.', _) => (Token::Dot, 1),
+            ('=', _) => (Token::Assign, 1),
+            ('@', _) => (Token::At, 1),
+            ('?', _) => (Token::Question, 1),
+            ('!', _) => (Token::Not, 1),
+            ('+', _) => (Token::Plus, 1),
+            ('-', _) => (Token::Minus, 1),
+            ('*', _) => (Token::Star, 1),
+            ('/', _) => (Token::Slash, 1),
+            ('<', _) => (Token::Less, 1),
+            ('>', _) => (Token::Greater, 1),
+            _ => return Err(self.error(format!("unexpected character `{}`", c))),
+        };
+        for _ in 0..len {
+            self.bump();
+        }
+        Ok(token)
+    }
+
+    /// Paths are `./a`, `../a`, `/a`, `~/a` or `a/b`; at least one `/` must be
+    /// followed by a path character.
+    fn path(&mut self) -> Option<String> {
+        let mut end = self.pos;
+        if self.peek(0) == Some('~') {
+            end += 1;
+        } else {
+            while self.chars.get(end).is_some_and(|c| is_path_char(*c)) {
+                end += 1;
+            }
+        }
+        let mut segments = 0;
+        while self.chars.get(end) == Some(&'/')
+            && self.chars.get(end + 1).is_some_and(|c| is_path_char(*c))
+        {
+            end += 1;
+            while self.chars.get(end).is_some_and(|c| is_path_char(*c)) {
+                end += 1;
+            }
+            segments += 1;
+        }
+        if segments == 0 {
+            return None;
+        }
+        let path: String = self.chars[self.pos..end].iter().collect();
+        for _ in self.pos..end {
+            self.bump();
+        }
+        Some(path)
+    }
+
+    fn search_path(&mut self) -> Option<String> {
+        let mut end = self.pos + 1;
+        while self
+            .chars
+            .get(end)
+            .is_some_and(|c| is_path_char(*c) || *c == '/')
+        {
+            end += 1;
+        }
+        if end == self.pos + 1 || self.chars.get(end) != Some(&'>') {
+            return None;
+        }
+        let path: String = self.chars[self.pos + 1..end].iter().collect();
+        for _ in self.pos..=end {
+            self.bump();
+        }
+        Some(path)
+    }
+
+    fn ident(&mut self) -> Token {
+        let mut ident = String::new();
+        while let Some(c) = self.peek(0).filter(|c| is_ident_char(*c)) {
+            ident.push(c);
+            self.bump();
+        }
+        match ident.as_str() {
+            "let" => Token::Let,
+            "in" => Token::In,
+            "rec" => Token::Rec,
+            "with" => Token::With,
+            "inherit" => Token::Inherit,
+            "if" => Token::If,
+            "then" => Token::Then,
+            "else" => Token::Else,
+            "assert" => Token::Assert,
+            _ => Token::Ident(ident),
+        }
+    }
+
+    fn number(&mut self) -> Result<Token, Error> {
+        let mut number = String::new();
+        while let Some(c) = self.peek(0).filter(|c| c.is_ascii_digit()) {
+            number.push(c);
+            self.bump();
+        }
+        let fraction =
+            self.peek(0) == Some('.') && self.peek(1).is_some_and(|c| c.is_ascii_digit());
+        if !fraction {
+            return number
+                .parse()
+                .map(Token::Int)
+                .map_err(|_| self.error(format!("integer `{}` out of range", number)));
+        }
+        number.push('.');
+        self.bump();
+        while let Some(c) = self.peek(0).filter(|c| c.is_ascii_digit()) {
+            number.push(c);
+            self.bump();
+        }
+        if let Some(e) = self.peek(0).filter(|c| *c == 'e' || *c == 'E') {
+            number.push(e);
+            self.bump();
+            if let Some(sign) = self.peek(0).filter(|c| *c == '+' || *c == '-') {
+                number.push(sign);
+                self.bump();
+            }
+            while let Some(c) = self.peek(0).filter(|c| c.is_ascii_digit()) {
+                number.push(c);
+                self.bump();
+            }
+        }
+        number
+            .parse()
+            .map(Token::Float)
+            .map_err(|_| self.error(format!("invalid float `{}`", number)))
+    }
+
+    fn string(&mut self) -> Result<Token, Error> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        loop {
+            match self.bump() {
+                None => return Err(self.error(String::from("unterminated string"))),
+                Some('"') => break,
+                Some('\\') => match self.bump() {
+                    Some('n') => literal.push('\n'),
+                    Some('r') => literal.push('\r'),
+                    Some('t') => literal.push('\t'),
+                    Some(c) => literal.push(c),
+                    None => return Err(self.error(String::from("unterminated string"))),
+                },
+                Some('$') if self.peek(0) == Some('$') && self.peek(1) == Some('{') => {
+                    self.bump();
+                    self.bump();
+                    literal.push_str("$${");
+                }
+                Some('$') if self.peek(0) == Some('{') => {
+                    self.bump();
+                    if !literal.is_empty() {
+                        parts.push(StrToken::Literal(std::mem::take(&mut literal)));
+                    }
+                    parts.push(StrToken::Interpolation(self.tokens(true)?));
+                }
+                Some(c) => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(StrToken::Literal(literal));
+        }
+        Ok(Token::Str(parts))
+    }
+
+    fn indented_string(&mut self) -> Result<Token, Error> {
+        // Characters are tagged with whether they came from an escape, so that
+        // escaped whitespace never counts as indentation.
+        let mut parts: Vec<IndentedPart> = Vec::new();
+        let mut literal: Vec<(char, bool)> = Vec::new();
+        loop {
+            match self.bump() {
+                None => return Err(self.error(String::from("unterminated indented string"))),
+                Some('\'') if self.peek(0) == Some('\'') => {
+                    self.bump();
+                    match self.peek(0) {
+                        Some('\'') => {
+                            self.bump();
+                            literal.push(('\'', true));
+                            literal.push(('\'', true));
+                        }
+                        Some('$') => {
+                            self.bump();
+                            literal.push(('$', true));
+                        }
+                        Some('\\') => {
+                            self.bump();
+                            match self.bump() {
+                                Some('n') => literal.push(('\n', true)),
+                                Some('r') => literal.push(('\r', true)),
+                                Some('t') => literal.push(('\t', true)),
+                                Some(c) => literal.push((c, true)),
+                                None => {
+                                    return Err(
+                                        self.error(String::from("unterminated indented string"))
+                                    )
+                                }
+                            }
+                        }
+                        _ => break,
+                    }
+                }
+                Some('$') if self.peek(0) == Some('$') && self.peek(1) == Some('{') => {
+                    self.bump();
+                    self.bump();
+                    literal.extend([('$', false), ('$', false), ('{', false)]);
+                }
+                Some('$') if self.peek(0) == Some('{') => {
+                    self.bump();
+                    parts.push(IndentedPart::Literal(std::mem::take(&mut literal)));
+                    parts.push(IndentedPart::Interpolation(self.tokens(true)?));
+                }
+                Some(c) => literal.push((c, false)),
+            }
+        }
+        parts.push(IndentedPart::Literal(literal));
+        Ok(Token::Str(strip_indentation(parts)))
+    }
+}
+
+enum IndentedPart {
+    Literal(Vec<(char, bool)>),
+    Interpolation(Vec<Spanned>),
+}
+
+fn strip_indentation(parts: Vec<IndentedPart>) -> Vec<StrToken> {
+    // Find the smallest indentation of any line that has content.
+    let mut indent = usize::MAX;
+    let mut current = 0;
+    let mut at_start = true;
+    for part in &parts {
+        match part {
+            IndentedPart::Literal(chars) => {
+                for (c, escaped) in chars {
+                    if at_start && *c == ' ' && !escaped {
+                        current += 1;
+                    } else if *c == '\n' && !escaped {
+                        at_start = true;
+                        current = 0;
+                    } else if at_start {
+                        indent = indent.min(current);
+                        at_start = false;
+                    }
+                }
+            }
+            IndentedPart::Interpolation(_) => {
+                if at_start {
+                    indent = indent.min(current);
+                    at_start = false;
+                }
+            }
+        }
+    }
+    if indent == usize::MAX {
+        indent = 0;
+    }
+
+    let count = parts.len();
+    let mut tokens = Vec::new();
+    let mut first_line = true;
+    let mut has_content = false;
+    let mut stripped = 0;
+    for (index, part) in parts.into_iter().enumerate() {
+        match part {
+            IndentedPart::Literal(chars) => {
+                let mut literal = String::new();
+                for (c, escaped) in chars {
+                    if c == '\n' && !escaped {
+                        // A first line holding only whitespace is dropped.
+                        if first_line && !has_content {
+                            literal.clear();
+                        } else {
+                            literal.push('\n');
+                        }
+                        first_line = false;
+                        has_content = false;
+                        stripped = 0;
+                        continue;
+                    }
+                    if !has_content && c == ' ' && !escaped && stripped < indent {
+                        stripped += 1;
+                        continue;
+                    }
+                    if c != ' ' || escaped {
+                        has_content = true;
+                    }
+                    literal.push(c);
+                }
+                // Whitespace on the line holding the closing `''` is dropped.
+                if index + 1 == count && !has_content {
+                    let newline = literal.rfind('\n').map_or(0, |newline| newline + 1);
+                    literal.truncate(newline);
+                }
+                if !literal.is_empty() {
+                    tokens.push(StrToken::Literal(literal));
+                }
+            }
+            IndentedPart::Interpolation(inner) => {
+                has_content = true;
+                tokens.push(StrToken::Interpolation(inner));
+            }
+        }
+    }
+    tokens
+}
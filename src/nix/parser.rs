@@ -0,0 +1,541 @@
+use crate::nix::expr::*;
+use crate::nix::lexer::{tokenize, Spanned, StrToken, Token};
+use std::fmt;
+
+/// A syntax error, with the 1-based line and column it was found at.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Error {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Parse a Nix expression.
+///
+/// ```
+/// use honey::nix::{self, Expr, StrPart};
+///
+/// let expr = nix::parse("{ cell, ... }: cell.nixosModules.machine1")?;
+///
+/// assert_eq!("{ cell, ... }: cell.nixosModules.machine1", expr.to_string());
+///
+/// let error = nix::parse("{ imports = [ a ]").unwrap_err();
+///
+/// assert_eq!("1:18: expected `;`, found end of input", error.to_string());
+///
+/// let error = nix::parse("\"λ\" + [ a").unwrap_err();
+///
+/// assert_eq!("1:10: expected `]`, found end of input", error.to_string());
+///
+/// // `$${` is not an interpolation and keeps both dollars.
+/// let expr = nix::parse(r#"[ "$${HOME}" ''$${HOME}'' ]"#)?;
+/// let literal = Expr::String(vec![StrPart::Literal(String::from("$${HOME}"))]);
+///
+/// assert_eq!(Expr::List(vec![literal.clone(), literal]), expr);
+/// # Ok::<_, nix::Error>(())
+/// ```
+pub fn parse(input: &str) -> Result<Expr, Error> {
+    let tokens = tokenize(input)?;
+    let line = input.matches('\n').count() + 1;
+    let column = input.chars().rev().take_while(|c| *c != '\n').count() + 1;
+    let mut parser = Parser {
+        tokens,
+        pos: 0,
+        end: (line, column),
+    };
+    let expr = parser.expr()?;
+    parser.expect_end()?;
+    Ok(expr)
+}
+
+struct Parser {
+    tokens: Vec<Spanned>,
+    pos: usize,
+    /// Position reported for errors at the end of input.
+    end: (usize, usize),
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.peek_nth(0)
+    }
+
+    fn peek_nth(&self, n: usize) -> Option<&Token> {
+        self.tokens.get(self.pos + n).map(|spanned| &spanned.token)
+    }
+
+    fn bump(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos)?.token.clone();
+        self.pos += 1;
+        Some(token)
+    }
+
+    fn error(&self, message: String) -> Error {
+        let (line, column) = match self.tokens.get(self.pos) {
+            Some(spanned) => (spanned.line, spanned.column),
+            None => self.end,
+        };
+        Error {
+            line,
+            column,
+            message,
+        }
+    }
+
+    fn found(&self) -> String {
+        match self.peek() {
+            Some(token) => token.describe(),
+            None => String::from("end of input"),
+        }
+    }
+
+    fn unexpected(&self, expected: &str) -> Error {
+        self.error(format!("expected {}, found {}", expected, self.found()))
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.peek() == Some(token) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &Token) -> Result<(), Error> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(self.unexpected(&token.describe()))
+        }
+    }
+
+    fn expect_end(&self) -> Result<(), Error> {
+        match self.peek() {
+            None => Ok(()),
+            Some(token) => Err(self.error(format!("unexpected {}", token.describe()))),
+        }
+    }
+
+    fn subparser(&self, tokens: Vec<Spanned>) -> Parser {
+        let end = match self.tokens.get(self.pos.saturating_sub(1)) {
+            Some(spanned) => (spanned.line, spanned.column),
+            None => self.end,
+        };
+        Parser {
+            tokens,
+            pos: 0,
+            end,
+        }
+    }
+
+    fn nested_expr(&self, tokens: Vec<Spanned>) -> Result<Expr, Error> {
+        let mut parser = self.subparser(tokens);
+        let expr = parser.expr()?;
+        parser.expect_end()?;
+        Ok(expr)
+    }
+
+    fn expr(&mut self) -> Result<Expr, Error> {
+        match self.peek() {
+            Some(Token::Ident(_)) if matches!(self.peek_nth(1), Some(Token::Colon | Token::At)) => {
+                self.lambda()
+            }
+            Some(Token::LBrace) if self.is_pattern() => self.lambda(),
+            Some(Token::Let) => {
+                self.bump();
+                let bindings = self.bindings(&Token::In)?;
+                self.expect(&Token::In)?;
+                let body = self.expr()?;
+                Ok(Expr::Let {
+                    bindings,
+                    body: Box::new(body),
+                })
+            }
+            Some(Token::With) => {
+                self.bump();
+                let namespace = self.expr()?;
+                self.expect(&Token::Semicolon)?;
+                let body = self.expr()?;
+                Ok(Expr::With {
+                    namespace: Box::new(namespace),
+                    body: Box::new(body),
+                })
+            }
+            Some(Token::Assert) => {
+                self.bump();
+                let condition = self.expr()?;
+                self.expect(&Token::Semicolon)?;
+                let body = self.expr()?;
+                Ok(Expr::Assert {
+                    condition: Box::new(condition),
+                    body: Box::new(body),
+                })
+            }
+            Some(Token::If) => {
+                self.bump();
+                let condition = self.expr()?;
+                self.expect(&Token::Then)?;
+                let consequent = self.expr()?;
+                self.expect(&Token::Else)?;
+                let alternative = self.expr()?;
+                Ok(Expr::If {
+                    condition: Box::new(condition),
+                    consequent: Box::new(consequent),
+                    alternative: Box::new(alternative),
+                })
+            }
+            _ => self.operation(0),
+        }
+    }
+
+    /// Whether the `{` at the cursor opens a lambda pattern rather than an
+    /// attribute set.
+    fn is_pattern(&self) -> bool {
+        match (self.peek_nth(1), self.peek_nth(2)) {
+            (Some(Token::RBrace), next) => matches!(next, Some(Token::Colon | Token::At)),
+            (Some(Token::Ellipsis), _) => true,
+            (Some(Token::Ident(_)), Some(Token::Comma | Token::Question)) => true,
+            (Some(Token::Ident(_)), Some(Token::RBrace)) => {
+                matches!(self.peek_nth(3), Some(Token::Colon | Token::At))
+            }
+            _ => false,
+        }
+    }
+
+    fn lambda(&mut self) -> Result<Expr, Error> {
+        let param = if let Some(Token::Ident(name)) = self.peek().cloned() {
+            self.bump();
+            if self.eat(&Token::At) {
+                self.pattern(Some(name))?
+            } else {
+                Param::Ident(name)
+            }
+        } else {
+            let pattern = self.pattern(None)?;
+            if self.eat(&Token::At) {
+                let name = self.ident()?;
+                match pattern {
+                    Param::Pattern {
+                        formals, ellipsis, ..
+                    } => Param::Pattern {
+                        formals,
+                        ellipsis,
+                        bind: Some(name),
+                    },
+                    param => param,
+                }
+            } else {
+                pattern
+            }
+        };
+        self.expect(&Token::Colon)?;
+        let body = self.expr()?;
+        Ok(Expr::Lambda {
+            param,
+            body: Box::new(body),
+        })
+    }
+
+    fn pattern(&mut self, bind: Option<String>) -> Result<Param, Error> {
+        self.expect(&Token::LBrace)?;
+        let mut formals = Vec::new();
+        let mut ellipsis = false;
+        loop {
+            if self.eat(&Token::RBrace) {
+                break;
+            }
+            if self.eat(&Token::Ellipsis) {
+                ellipsis = true;
+                self.expect(&Token::RBrace)?;
+                break;
+            }
+            let name = self.ident()?;
+            let default = if self.eat(&Token::Question) {
+                Some(self.expr()?)
+            } else {
+                None
+            };
+            formals.push(Formal { name, default });
+            if !self.eat(&Token::Comma) {
+                self.expect(&Token::RBrace)?;
+                break;
+            }
+        }
+        Ok(Param::Pattern {
+            formals,
+            ellipsis,
+            bind,
+        })
+    }
+
+    fn ident(&mut self) -> Result<String, Error> {
+        match self.peek().cloned() {
+            Some(Token::Ident(name)) => {
+                self.bump();
+                Ok(name)
+            }
+            _ => Err(self.unexpected("identifier")),
+        }
+    }
+
+    fn binary_op(token: &Token) -> Option<(BinaryOp, u8, u8)> {
+        // (operator, left binding power, right binding power)
+        let op = match token {
+            Token::Implication => (BinaryOp::Implication, 1, 1),
+            Token::Or => (BinaryOp::Or, 2, 3),
+            Token::And => (BinaryOp::And, 3, 4),
+            Token::Eq => (BinaryOp::Eq, 4, 5),
+            Token::NotEq => (BinaryOp::NotEq, 4, 5),
+            Token::Less => (BinaryOp::Less, 5, 6),
+            Token::LessEq => (BinaryOp::LessEq, 5, 6),
+            Token::Greater => (BinaryOp::Greater, 5, 6),
+            Token::GreaterEq => (BinaryOp::GreaterEq, 5, 6),
+            Token::Update => (BinaryOp::Update, 6, 6),
+            Token::Plus => (BinaryOp::Add, 8, 9),
+            Token::Minus => (BinaryOp::Sub, 8, 9),
+            Token::Star => (BinaryOp::Mul, 9, 10),
+            Token::Slash => (BinaryOp::Div, 9, 10),
+            Token::Concat => (BinaryOp::Concat, 10, 10),
+            _ => return None,
+        };
+        Some(op)
+    }
+
+    fn operation(&mut self, min: u8) -> Result<Expr, Error> {
+        let mut lhs = match self.peek() {
+            Some(Token::Not) => {
+                self.bump();
+                let expr = self.operation(8)?;
+                Expr::Unary {
+                    op: UnaryOp::Not,
+                    expr: Box::new(expr),
+                }
+            }
+            Some(Token::Minus) => {
+                self.bump();
+                let expr = self.apply()?;
+                Expr::Unary {
+                    op: UnaryOp::Negate,
+                    expr: Box::new(expr),
+                }
+            }
+            _ => self.apply()?,
+        };
+        while let Some(token) = self.peek().cloned() {
+            if token == Token::Question {
+                if min > 11 {
+                    break;
+                }
+                self.bump();
+                let path = self.attr_path()?;
+                lhs = Expr::HasAttr {
+                    expr: Box::new(lhs),
+                    path,
+                };
+                continue;
+            }
+            let (op, left, right) = match Self::binary_op(&token) {
+                Some(op) => op,
+                None => break,
+            };
+            if left < min {
+                break;
+            }
+            self.bump();
+            let rhs = self.operation(right)?;
+            lhs = Expr::Binary {
+                op,
+                lhs: Box::new(lhs),
+                rhs: Box::new(rhs),
+            };
+        }
+        Ok(lhs)
+    }
+
+    fn starts_argument(&self) -> bool {
+        match self.peek() {
+            Some(Token::Ident(name)) => name != "or",
+            Some(
+                Token::Int(_)
+                | Token::Float(_)
+                | Token::Str(_)
+                | Token::Path(_)
+                | Token::SearchPath(_)
+                | Token::LParen
+                | Token::LBracket
+                | Token::LBrace
+                | Token::Rec,
+            ) => true,
+            _ => false,
+        }
+    }
+
+    fn apply(&mut self) -> Result<Expr, Error> {
+        let mut function = self.select()?;
+        while self.starts_argument() {
+            let argument = self.select()?;
+            function = Expr::Apply {
+                function: Box::new(function),
+                argument: Box::new(argument),
+            };
+        }
+        Ok(function)
+    }
+
+    fn select(&mut self) -> Result<Expr, Error> {
+        let expr = self.primary()?;
+        if !self.eat(&Token::Dot) {
+            return Ok(expr);
+        }
+        let path = self.attr_path()?;
+        let default = match self.peek() {
+            Some(Token::Ident(name)) if name == "or" => {
+                self.bump();
+                Some(Box::new(self.select()?))
+            }
+            _ => None,
+        };
+        Ok(Expr::Select {
+            expr: Box::new(expr),
+            path,
+            default,
+        })
+    }
+
+    fn primary(&mut self) -> Result<Expr, Error> {
+        let token = match self.peek() {
+            Some(token) => token.clone(),
+            None => return Err(self.unexpected("expression")),
+        };
+        let expr = match token {
+            Token::Ident(name) => {
+                self.bump();
+                Expr::Ident(name)
+            }
+            Token::Int(value) => {
+                self.bump();
+                Expr::Int(value)
+            }
+            Token::Float(value) => {
+                self.bump();
+                Expr::Float(value)
+            }
+            Token::Path(path) => {
+                self.bump();
+                Expr::Path(path)
+            }
+            Token::SearchPath(path) => {
+                self.bump();
+                Expr::SearchPath(path)
+            }
+            Token::Str(parts) => {
+                self.bump();
+                Expr::String(self.string_parts(parts)?)
+            }
+            Token::LParen => {
+                self.bump();
+                let expr = self.expr()?;
+                self.expect(&Token::RParen)?;
+                expr
+            }
+            Token::LBracket => {
+                self.bump();
+                let mut items = Vec::new();
+                while !self.eat(&Token::RBracket) {
+                    if self.peek().is_none() {
+                        return Err(self.unexpected("`]`"));
+                    }
+                    items.push(self.select()?);
+                }
+                Expr::List(items)
+            }
+            Token::Rec => {
+                self.bump();
+                self.attr_set(true)?
+            }
+            Token::LBrace => self.attr_set(false)?,
+            _ => return Err(self.unexpected("expression")),
+        };
+        Ok(expr)
+    }
+
+    fn string_parts(&self, parts: Vec<StrToken>) -> Result<Vec<StrPart>, Error> {
+        parts
+            .into_iter()
+            .map(|part| match part {
+                StrToken::Literal(literal) => Ok(StrPart::Literal(literal)),
+                StrToken::Interpolation(tokens) => {
+                    Ok(StrPart::Interpolation(self.nested_expr(tokens)?))
+                }
+            })
+            .collect()
+    }
+
+    fn attr_set(&mut self, recursive: bool) -> Result<Expr, Error> {
+        self.expect(&Token::LBrace)?;
+        let bindings = self.bindings(&Token::RBrace)?;
+        self.expect(&Token::RBrace)?;
+        Ok(Expr::AttrSet {
+            recursive,
+            bindings,
+        })
+    }
+
+    fn bindings(&mut self, until: &Token) -> Result<Vec<Binding>, Error> {
+        let mut bindings = Vec::new();
+        while self.peek() != Some(until) {
+            if self.peek().is_none() {
+                return Err(self.unexpected(&until.describe()));
+            }
+            if self.eat(&Token::Inherit) {
+                let from = if self.eat(&Token::LParen) {
+                    let from = self.expr()?;
+                    self.expect(&Token::RParen)?;
+                    Some(from)
+                } else {
+                    None
+                };
+                let mut names = Vec::new();
+                while !self.eat(&Token::Semicolon) {
+                    names.push(self.attr_name()?);
+                }
+                bindings.push(Binding::Inherit { from, names });
+            } else {
+                let path = self.attr_path()?;
+                self.expect(&Token::Assign)?;
+                let value = self.expr()?;
+                self.expect(&Token::Semicolon)?;
+                bindings.push(Binding::Attr { path, value });
+            }
+        }
+        Ok(bindings)
+    }
+
+    fn attr_path(&mut self) -> Result<Vec<AttrName>, Error> {
+        let mut path = vec![self.attr_name()?];
+        while self.eat(&Token::Dot) {
+            path.push(self.attr_name()?);
+        }
+        Ok(path)
+    }
+
+    fn attr_name(&mut self) -> Result<AttrName, Error> {
+        let name = match self.peek().cloned() {
+            Some(Token::Ident(name)) => AttrName::Ident(name),
+            Some(Token::Str(parts)) => AttrName::String(self.string_parts(parts)?),
+            Some(Token::Interpolate(tokens)) => AttrName::Interpolation(self.nested_expr(tokens)?),
+            _ => return Err(self.unexpected("attribute name")),
+        };
+        self.bump();
+        Ok(name)
+    }
+}
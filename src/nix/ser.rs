@@ -0,0 +1,850 @@
+use crate::nix::value::{NixValue, StringPart};
+use genco::prelude::*;
+use serde::ser::{self, Serialize};
+use std::fmt;
+
+/// Error returned when a value has no Nix representation.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Error(String);
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl ser::Error for Error {
+    fn custom<T>(message: T) -> Self
+    where
+        T: fmt::Display,
+    {
+        Error(message.to_string())
+    }
+}
+
+/// Case of struct field and enum variant names. Map keys are data and kept
+/// as they are.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Case {
+    /// As serde names them, `host_name` and `Intel`.
+    #[default]
+    Preserve,
+    /// `hostName` and `intel`, as NixOS options are named.
+    Camel,
+    /// `host-name` and `intel`.
+    Kebab,
+}
+
+impl Case {
+    fn apply(&self, name: &str) -> String {
+        if *self == Case::Preserve {
+            return name.to_string();
+        }
+        // Words of `snake_case` and `PascalCase` names.
+        let mut words: Vec<String> = Vec::new();
+        let mut previous: Option<char> = None;
+        for c in name.chars() {
+            let boundary = c.is_uppercase()
+                && previous
+                    .is_some_and(|previous| previous.is_lowercase() || previous.is_ascii_digit());
+            if c == '_' || c == '-' {
+                words.push(String::new());
+            } else {
+                if boundary || words.is_empty() {
+                    words.push(String::new());
+                }
+                if let Some(word) = words.last_mut() {
+                    word.extend(c.to_lowercase());
+                }
+            }
+            previous = Some(c);
+        }
+        words.retain(|word| !word.is_empty());
+        match self {
+            Case::Preserve => unreachable!(),
+            Case::Kebab => words.join("-"),
+            Case::Camel => {
+                let mut camel = String::new();
+                for (i, word) in words.iter().enumerate() {
+                    let mut chars = word.chars();
+                    match chars.next() {
+                        Some(first) if i > 0 => {
+                            camel.extend(first.to_uppercase());
+                            camel.push_str(chars.as_str());
+                        }
+                        _ => camel.push_str(word),
+                    }
+                }
+                camel
+            }
+        }
+    }
+}
+
+/// How enum variants are written.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum Enums {
+    /// Unit variants as strings, `"intel"`; variants with data as
+    /// `{ intel = data; }`.
+    #[default]
+    String,
+    /// Every variant as an attrset naming it in the given attribute, e.g.
+    /// `{ type = "intel"; }`. Struct variants add their fields, the others
+    /// their data as `value`. A field named like the tag is an error:
+    ///
+    /// ```
+    /// use honey::nix::ser::{to_tokens1, Enums, Options};
+    /// use serde::Serialize;
+    ///
+    /// #[derive(Serialize)]
+    /// enum Microcode {
+    ///     Vendor { name: String, r#type: String },
+    ///     Other(String),
+    /// }
+    ///
+    /// let options = Options {
+    ///     enums: Enums::Tagged(String::from("type")),
+    ///     ..Options::default()
+    /// };
+    /// let vendor = Microcode::Vendor {
+    ///     name: String::from("amd"),
+    ///     r#type: String::from("x86"),
+    /// };
+    /// assert!(to_tokens1(&vendor, &options).is_err());
+    ///
+    /// let options = Options {
+    ///     enums: Enums::Tagged(String::from("value")),
+    ///     ..Options::default()
+    /// };
+    /// assert!(to_tokens1(&Microcode::Other(String::from("arm")), &options).is_err());
+    /// ```
+    Tagged(String),
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Options {
+    pub case: Case,
+    /// Leave out struct fields and map entries that are `None`, rather than
+    /// writing `null`.
+    pub skip_none: bool,
+    pub enums: Enums,
+}
+
+/// Turn a value into Nix with the default options.
+///
+/// ```
+/// use genco::prelude::*;
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct Host {
+///     host_name: String,
+///     tags: Vec<&'static str>,
+///     cores: u32,
+///     location: Option<String>,
+/// }
+///
+/// let host = Host {
+///     host_name: String::from("machine00"),
+///     tags: vec!["web"],
+///     cores: 8,
+///     location: None,
+/// };
+///
+/// let toks = honey::nix::to_tokens(&host)?;
+///
+/// assert_eq!(
+///     vec![
+///         "{",
+///         "    ...",
+///         "}:",
+///         "",
+///         "{",
+///         "    host_name = \"machine00\";",
+///         "    tags = [",
+///         "        \"web\"",
+///         "    ];",
+///         "    cores = 8;",
+///         "    location = null;",
+///         "}",
+///     ],
+///     toks.to_file_vec()?
+/// );
+/// # Ok::<_, Box<dyn std::error::Error>>(())
+/// ```
+pub fn to_tokens<T>(value: &T) -> Result<nix::Tokens, Error>
+where
+    T: Serialize + ?Sized,
+{
+    to_tokens1(value, &Options::default())
+}
+
+/// Turn a value into Nix.
+///
+/// ```
+/// use genco::prelude::*;
+/// use honey::nix::ser::{to_tokens1, Case, Enums, Options};
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// enum Microcode {
+///     Intel,
+///     Vendor { name: String },
+/// }
+///
+/// #[derive(Serialize)]
+/// struct Host {
+///     host_name: String,
+///     state_version: Option<String>,
+///     microcode: Microcode,
+///     cores: i64,
+///     ratio: f64,
+///     tags: Vec<String>,
+/// }
+///
+/// let options = Options {
+///     case: Case::Camel,
+///     skip_none: true,
+///     enums: Enums::Tagged(String::from("type")),
+/// };
+///
+/// let host = Host {
+///     host_name: String::from("web.prod"),
+///     state_version: None,
+///     microcode: Microcode::Intel,
+///     cores: 8,
+///     ratio: 0.5,
+///     tags: vec![String::from("${web}")],
+/// };
+///
+/// assert_eq!(
+///     vec![
+///         "{",
+///         "    ...",
+///         "}:",
+///         "",
+///         "{",
+///         "    hostName = \"web.prod\";",
+///         "    microcode = {",
+///         "        type = \"intel\";",
+///         "    };",
+///         "    cores = 8;",
+///         "    ratio = 0.5;",
+///         "    tags = [",
+///         "        \"\\${web}\"",
+///         "    ];",
+///         "}",
+///     ],
+///     to_tokens1(&host, &options)?.to_file_vec()?
+/// );
+///
+/// let host = Host {
+///     host_name: String::from("é \"x\"\n"),
+///     state_version: Some(String::from("23.05")),
+///     microcode: Microcode::Vendor { name: String::from("amd") },
+///     cores: -3,
+///     ratio: -1e300,
+///     tags: vec![],
+/// };
+///
+/// assert_eq!(
+///     vec![
+///         "{",
+///         "    ...",
+///         "}:",
+///         "",
+///         "{",
+///         "    host_name = \"é \\\"x\\\"\\n\";",
+///         "    state_version = \"23.05\";",
+///         "    microcode = {",
+///         "        Vendor = {",
+///         "            name = \"amd\";",
+///         "        };",
+///         "    };",
+///         "    cores = -3;",
+///         "    ratio = -1.0e300;",
+///         "    tags = [];",
+///         "}",
+///     ],
+///     to_tokens1(&host, &Options::default())?.to_file_vec()?
+/// );
+/// # Ok::<_, Box<dyn std::error::Error>>(())
+/// ```
+///
+/// Rendered values read back through `honey::nix::parse` as the same data:
+///
+/// ```
+/// use genco::prelude::*;
+/// use honey::nix::ser::{to_tokens1, Case, Enums, Options};
+/// use honey::nix::{Binding, Expr, StrPart, UnaryOp};
+/// use proptest::prelude::*;
+/// use proptest::test_runner::TestRunner;
+/// use serde::Serialize;
+/// use serde_json::{json, Value};
+///
+/// /// The parsed expression as JSON, to compare with the serialized value.
+/// fn json(expr: &Expr) -> Value {
+///     match expr {
+///         Expr::Ident(name) if name == "null" => Value::Null,
+///         Expr::Ident(name) => json!(name == "true"),
+///         Expr::Int(value) => json!(value),
+///         Expr::Float(value) => json!(value),
+///         Expr::Unary { op: UnaryOp::Negate, expr } => match json(expr) {
+///             Value::Number(n) if n.is_i64() => json!(-n.as_i64().unwrap()),
+///             Value::Number(n) => json!(-n.as_f64().unwrap()),
+///             value => panic!("{}", value),
+///         },
+///         Expr::String(parts) => match parts.as_slice() {
+///             [] => json!(""),
+///             [StrPart::Literal(literal)] => json!(literal),
+///             parts => panic!("{:?}", parts),
+///         },
+///         Expr::List(items) => Value::Array(items.iter().map(json).collect()),
+///         Expr::AttrSet { bindings, .. } => Value::Object(
+///             bindings
+///                 .iter()
+///                 .map(|binding| match binding {
+///                     Binding::Attr { path, value } => (path[0].as_str().unwrap().to_string(), json(value)),
+///                     binding => panic!("{}", binding),
+///                 })
+///                 .collect(),
+///         ),
+///         expr => panic!("{}", expr),
+///     }
+/// }
+///
+/// #[derive(Serialize)]
+/// enum Loader {
+///     SystemdBoot,
+///     Grub { device: String },
+/// }
+///
+/// #[derive(Serialize)]
+/// enum Boot {
+///     Bios,
+///     Uefi(Loader),
+/// }
+///
+/// #[derive(Serialize)]
+/// struct Host {
+///     host_name: String,
+///     state_version: Option<String>,
+///     boot: Boot,
+///     cores: i64,
+///     ratio: f64,
+///     enable_ssh: bool,
+///     tags: Vec<String>,
+/// }
+///
+/// let options = Options {
+///     case: Case::Camel,
+///     skip_none: true,
+///     enums: Enums::Tagged(String::from("type")),
+/// };
+///
+/// let host = Host {
+///     host_name: String::from("web.prod"),
+///     state_version: None,
+///     boot: Boot::Uefi(Loader::Grub { device: String::from("${disk}") }),
+///     cores: 8,
+///     ratio: -0.5,
+///     enable_ssh: true,
+///     tags: vec![String::from("${web}"), String::from("$${x} '' \"y\"")],
+/// };
+/// let input = to_tokens1(&host, &options)?.to_string()?;
+///
+/// assert_eq!(
+///     json!({
+///         "hostName": "web.prod",
+///         "boot": { "type": "uefi", "value": { "type": "grub", "device": "${disk}" } },
+///         "cores": 8,
+///         "ratio": -0.5,
+///         "enableSsh": true,
+///         "tags": ["${web}", "$${x} '' \"y\""],
+///     }),
+///     json(&honey::nix::parse(&input)?)
+/// );
+///
+/// let strategy = (
+///     (any::<String>(), any::<Option<String>>(), any::<Option<String>>()),
+///     (-(1i64 << 62)..(1i64 << 62), -1e300..1e300f64, any::<bool>(), any::<Vec<String>>()),
+/// );
+///
+/// TestRunner::default().run(&strategy, |((name, version, device), (cores, ratio, enable_ssh, tags))| {
+///     let boot = match device {
+///         None if enable_ssh => Boot::Bios,
+///         None => Boot::Uefi(Loader::SystemdBoot),
+///         Some(device) => Boot::Uefi(Loader::Grub { device }),
+///     };
+///     let host = Host { host_name: name, state_version: version, boot, cores, ratio, enable_ssh, tags };
+///     let input = honey::nix::to_tokens(&host).unwrap().to_string().unwrap();
+///     let parsed = honey::nix::parse(&input).map_err(|error| TestCaseError::fail(format!("{}: {}", error, input)))?;
+///     prop_assert_eq!(serde_json::to_value(&host).unwrap(), json(&parsed));
+///     Ok(())
+/// }).unwrap();
+/// # Ok::<_, Box<dyn std::error::Error>>(())
+/// ```
+pub fn to_tokens1<T>(value: &T, options: &Options) -> Result<nix::Tokens, Error>
+where
+    T: Serialize + ?Sized,
+{
+    Ok(to_value(value, options)?.into())
+}
+
+/// Turn a value into a `NixValue`, e.g. for the value of a `Variable`.
+pub fn to_value<T>(value: &T, options: &Options) -> Result<NixValue, Error>
+where
+    T: Serialize + ?Sized,
+{
+    Ok(value
+        .serialize(Serializer { options })?
+        .unwrap_or(NixValue::Null))
+}
+
+/// Serializes into a `NixValue`, `None` standing for an `Option::None`.
+struct Serializer<'a> {
+    options: &'a Options,
+}
+
+type Attrs = Vec<(Vec<String>, NixValue)>;
+
+impl<'a> Serializer<'a> {
+    fn variant(&self, variant: &str, value: Option<NixValue>) -> Result<Option<NixValue>, Error> {
+        let name = self.options.case.apply(variant);
+        let attrs = match (&self.options.enums, value) {
+            (Enums::String, None) => return Ok(Some(name.into())),
+            (Enums::String, Some(value)) => vec![(vec![name], value)],
+            (Enums::Tagged(tag), None) => vec![(vec![tag.clone()], name.into())],
+            (Enums::Tagged(tag), Some(_)) if tag == "value" => {
+                return Err(Error(format!(
+                    "data of variant `{}` clashes with the enum tag `value`",
+                    variant
+                )))
+            }
+            (Enums::Tagged(tag), Some(value)) => vec![
+                (vec![tag.clone()], name.into()),
+                (vec![String::from("value")], value),
+            ],
+        };
+        Ok(Some(NixValue::AttrSet(attrs)))
+    }
+
+    fn struct_variant(&self, variant: &str, fields: Attrs) -> Result<Option<NixValue>, Error> {
+        match &self.options.enums {
+            Enums::String => self.variant(variant, Some(NixValue::AttrSet(fields))),
+            Enums::Tagged(tag) => {
+                if fields
+                    .iter()
+                    .any(|(path, _)| path == std::slice::from_ref(tag))
+                {
+                    return Err(Error(format!(
+                        "field `{}` of variant `{}` clashes with the enum tag",
+                        tag, variant
+                    )));
+                }
+                let variant = self.options.case.apply(variant);
+                let mut attrs = vec![(vec![tag.clone()], variant.into())];
+                attrs.extend(fields);
+                Ok(Some(NixValue::AttrSet(attrs)))
+            }
+        }
+    }
+}
+
+fn float(value: f64) -> Result<Option<NixValue>, Error> {
+    if value.is_finite() {
+        Ok(Some(NixValue::Float(value)))
+    } else {
+        Err(Error(format!(
+            "{} can not be written as a Nix float",
+            value
+        )))
+    }
+}
+
+impl<'a> ser::Serializer for Serializer<'a> {
+    type Ok = Option<NixValue>;
+    type Error = Error;
+    type SerializeSeq = Seq<'a>;
+    type SerializeTuple = Seq<'a>;
+    type SerializeTupleStruct = Seq<'a>;
+    type SerializeTupleVariant = Seq<'a>;
+    type SerializeMap = Map<'a>;
+    type SerializeStruct = Map<'a>;
+    type SerializeStructVariant = Map<'a>;
+
+    fn serialize_bool(self, value: bool) -> Result<Self::Ok, Error> {
+        Ok(Some(value.into()))
+    }
+
+    fn serialize_i8(self, value: i8) -> Result<Self::Ok, Error> {
+        self.serialize_i64(value.into())
+    }
+
+    fn serialize_i16(self, value: i16) -> Result<Self::Ok, Error> {
+        self.serialize_i64(value.into())
+    }
+
+    fn serialize_i32(self, value: i32) -> Result<Self::Ok, Error> {
+        self.serialize_i64(value.into())
+    }
+
+    fn serialize_i64(self, value: i64) -> Result<Self::Ok, Error> {
+        Ok(Some(value.into()))
+    }
+
+    fn serialize_u8(self, value: u8) -> Result<Self::Ok, Error> {
+        self.serialize_i64(value.into())
+    }
+
+    fn serialize_u16(self, value: u16) -> Result<Self::Ok, Error> {
+        self.serialize_i64(value.into())
+    }
+
+    fn serialize_u32(self, value: u32) -> Result<Self::Ok, Error> {
+        self.serialize_i64(value.into())
+    }
+
+    fn serialize_u64(self, value: u64) -> Result<Self::Ok, Error> {
+        match i64::try_from(value) {
+            Ok(value) => self.serialize_i64(value),
+            Err(_) => Err(Error(format!("{} is too large for a Nix integer", value))),
+        }
+    }
+
+    fn serialize_f32(self, value: f32) -> Result<Self::Ok, Error> {
+        float(value.into())
+    }
+
+    fn serialize_f64(self, value: f64) -> Result<Self::Ok, Error> {
+        float(value)
+    }
+
+    fn serialize_char(self, value: char) -> Result<Self::Ok, Error> {
+        Ok(Some(value.to_string().into()))
+    }
+
+    fn serialize_str(self, value: &str) -> Result<Self::Ok, Error> {
+        Ok(Some(value.into()))
+    }
+
+    fn serialize_bytes(self, value: &[u8]) -> Result<Self::Ok, Error> {
+        let bytes = value
+            .iter()
+            .map(|byte| i64::from(*byte))
+            .collect::<Vec<_>>();
+        Ok(Some(bytes.into()))
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok, Error> {
+        Ok(None)
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok, Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok, Error> {
+        Ok(Some(NixValue::Null))
+    }
+
+    fn serialize_unit_struct(self, _: &'static str) -> Result<Self::Ok, Error> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+    ) -> Result<Self::Ok, Error> {
+        self.variant(variant, None)
+    }
+
+    fn serialize_newtype_struct<T>(self, _: &'static str, value: &T) -> Result<Self::Ok, Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok, Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        let value = to_value(value, self.options)?;
+        self.variant(variant, Some(value))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, Error> {
+        Ok(Seq {
+            options: self.options,
+            variant: None,
+            values: Vec::with_capacity(len.unwrap_or_default()),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Error> {
+        Ok(Seq {
+            options: self.options,
+            variant: Some(variant),
+            values: Vec::with_capacity(len),
+        })
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> Result<Self::SerializeMap, Error> {
+        Ok(Map {
+            options: self.options,
+            variant: None,
+            key: None,
+            attrs: Vec::new(),
+        })
+    }
+
+    fn serialize_struct(self, _: &'static str, _: usize) -> Result<Self::SerializeStruct, Error> {
+        self.serialize_map(None)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStructVariant, Error> {
+        Ok(Map {
+            options: self.options,
+            variant: Some(variant),
+            key: None,
+            attrs: Vec::new(),
+        })
+    }
+}
+
+struct Seq<'a> {
+    options: &'a Options,
+    variant: Option<&'static str>,
+    values: Vec<NixValue>,
+}
+
+impl<'a> Seq<'a> {
+    fn push<T>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.values.push(to_value(value, self.options)?);
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Option<NixValue>, Error> {
+        let list = NixValue::List(self.values);
+        match self.variant {
+            Some(variant) => Serializer {
+                options: self.options,
+            }
+            .variant(variant, Some(list)),
+            None => Ok(Some(list)),
+        }
+    }
+}
+
+impl<'a> ser::SerializeSeq for Seq<'a> {
+    type Ok = Option<NixValue>;
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Error> {
+        self.finish()
+    }
+}
+
+impl<'a> ser::SerializeTuple for Seq<'a> {
+    type Ok = Option<NixValue>;
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Error> {
+        self.finish()
+    }
+}
+
+impl<'a> ser::SerializeTupleStruct for Seq<'a> {
+    type Ok = Option<NixValue>;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Error> {
+        self.finish()
+    }
+}
+
+impl<'a> ser::SerializeTupleVariant for Seq<'a> {
+    type Ok = Option<NixValue>;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Error> {
+        self.finish()
+    }
+}
+
+struct Map<'a> {
+    options: &'a Options,
+    variant: Option<&'static str>,
+    key: Option<String>,
+    attrs: Attrs,
+}
+
+impl<'a> Map<'a> {
+    fn insert<T>(&mut self, name: String, value: &T) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        if self.attrs.iter().any(|(path, _)| path[0] == name) {
+            return Err(Error(format!("duplicate attribute `{}`", name)));
+        }
+        match value.serialize(Serializer {
+            options: self.options,
+        })? {
+            Some(value) => self.attrs.push((vec![name], value)),
+            None if self.options.skip_none => (),
+            None => self.attrs.push((vec![name], NixValue::Null)),
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Option<NixValue>, Error> {
+        let serializer = Serializer {
+            options: self.options,
+        };
+        match self.variant {
+            Some(variant) => serializer.struct_variant(variant, self.attrs),
+            None => Ok(Some(NixValue::AttrSet(self.attrs))),
+        }
+    }
+}
+
+impl<'a> ser::SerializeMap for Map<'a> {
+    type Ok = Option<NixValue>;
+    type Error = Error;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        self.key = match to_value(key, self.options)? {
+            NixValue::String(parts) => match parts.as_slice() {
+                [StringPart::Literal(key)] => Some(key.clone()),
+                _ => None,
+            },
+            NixValue::Int(key) => Some(key.to_string()),
+            NixValue::Bool(key) => Some(key.to_string()),
+            _ => None,
+        };
+        match self.key {
+            Some(_) => Ok(()),
+            None => Err(Error(String::from("map keys must be strings or numbers"))),
+        }
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        match self.key.take() {
+            Some(key) => self.insert(key, value),
+            None => Err(Error(String::from("map value without a key"))),
+        }
+    }
+
+    fn end(self) -> Result<Self::Ok, Error> {
+        self.finish()
+    }
+}
+
+impl<'a> ser::SerializeStruct for Map<'a> {
+    type Ok = Option<NixValue>;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, name: &'static str, value: &T) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        let name = self.options.case.apply(name);
+        self.insert(name, value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Error> {
+        self.finish()
+    }
+}
+
+impl<'a> ser::SerializeStructVariant for Map<'a> {
+    type Ok = Option<NixValue>;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, name: &'static str, value: &T) -> Result<(), Error>
+    where
+        T: Serialize + ?Sized,
+    {
+        let name = self.options.case.apply(name);
+        self.insert(name, value)
+    }
+
+    fn end(self) -> Result<Self::Ok, Error> {
+        self.finish()
+    }
+}
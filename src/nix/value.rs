@@ -0,0 +1,448 @@
+use crate::hive::attrs::attr_name;
+use crate::hive::inherit::{self, Inherited};
+use genco::prelude::*;
+
+/// A part of a string value.
+#[derive(Clone, Debug, PartialEq)]
+pub enum StringPart {
+    Literal(String),
+    /// `${value}`
+    Interpolation(NixValue),
+}
+
+/// The parameter of a lambda.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Param {
+    /// `x: ...`
+    Ident(String),
+    /// `{ a, b, ... }: ...`
+    Pattern {
+        formals: Vec<String>,
+        ellipsis: bool,
+    },
+}
+
+/// A Nix value to generate, escaped and quoted where needed.
+///
+/// `Argument`, `Inherit` and `Variable` render as a bare name and hoist
+/// their definition into the `{ cell, inputs, ... }:` pattern and the `let`
+/// block, like the genco imports they are built on.
+///
+/// ```
+/// use genco::prelude::*;
+/// use honey::nix::value::{NixValue, Param, StringPart};
+///
+/// let pkgs = NixValue::select(NixValue::inherit("inputs", "nixpkgs"), "legacyPackages");
+///
+/// let value = NixValue::AttrSet(vec![
+///     (vec![String::from("greeting")], NixValue::String(vec![
+///         StringPart::Literal(String::from("hello \"${user}\" from ")),
+///         StringPart::Interpolation(NixValue::Ident(String::from("host"))),
+///     ])),
+///     (vec![String::from("packages")], NixValue::apply(
+///         NixValue::Lambda {
+///             param: Param::Pattern { formals: vec![String::from("pkgs")], ellipsis: true },
+///             body: Box::new(NixValue::With {
+///                 namespace: Box::new(NixValue::Ident(String::from("pkgs"))),
+///                 body: Box::new(NixValue::List(vec![NixValue::Ident(String::from("git"))])),
+///             }),
+///         },
+///         pkgs,
+///     )),
+///     (vec![String::from("web.prod"), String::from("enable")], true.into()),
+///     (vec![String::from("ratio")], 0.5.into()),
+///     (vec![String::from("source")], NixValue::Path(String::from("./source"))),
+///     (vec![String::from("extra")], NixValue::Null),
+/// ]);
+///
+/// let toks = quote!($value);
+///
+/// assert_eq!(
+///     vec![
+///         "{",
+///         "    inputs,",
+///         "    ...",
+///         "}:",
+///         "",
+///         "let",
+///         "    inherit (inputs) nixpkgs;",
+///         "in",
+///         "",
+///         "{",
+///         "    greeting = \"hello \\\"\\${user}\\\" from ${host}\";",
+///         "    packages = ({ pkgs, ... }: with pkgs; [",
+///         "        git",
+///         "    ]) nixpkgs.legacyPackages;",
+///         "    \"web.prod\".enable = true;",
+///         "    ratio = 0.5;",
+///         "    source = ./source;",
+///         "    extra = null;",
+///         "}",
+///     ],
+///     toks.to_file_vec()?
+/// );
+/// # Ok::<_, genco::fmt::Error>(())
+/// ```
+///
+/// Nix has no literal for infinity and NaN, they are written as expressions
+/// evaluating to them:
+///
+/// ```
+/// use genco::prelude::*;
+/// use honey::nix::NixValue;
+///
+/// let value = NixValue::List(vec![f64::INFINITY.into(), f64::NEG_INFINITY.into(), f64::NAN.into()]);
+///
+/// assert_eq!(
+///     "[\n    (1.0e308 * 10)\n    (-1.0e308 * 10)\n    (1.0e308 * 10 - 1.0e308 * 10)\n]",
+///     quote!($value).to_string()?
+/// );
+/// # Ok::<_, genco::fmt::Error>(())
+/// ```
+///
+/// `let` binding names that are not identifiers are quoted, as attribute
+/// names are:
+///
+/// ```
+/// use genco::prelude::*;
+/// use honey::nix::NixValue;
+///
+/// let value = NixValue::Let {
+///     bindings: vec![
+///         (String::from("web.prod"), NixValue::Null),
+///         (String::from("web"), NixValue::Bool(true)),
+///     ],
+///     body: Box::new(NixValue::Ident(String::from("web"))),
+/// };
+///
+/// let input = quote!($value).to_string()?;
+///
+/// assert_eq!("let\n    \"web.prod\" = null;\n    web = true;\nin web", input);
+/// assert!(honey::nix::parse(&input).is_ok());
+/// # Ok::<_, Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub enum NixValue {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(Vec<StringPart>),
+    Path(String),
+    List(Vec<NixValue>),
+    /// Attribute paths with their values, in order.
+    AttrSet(Vec<(Vec<String>, NixValue)>),
+    RecAttrSet(Vec<(Vec<String>, NixValue)>),
+    Ident(String),
+    /// An argument of the file, such as `cell`.
+    Argument(String),
+    /// `inherit (path) name;` in the `let` block.
+    Inherit {
+        path: String,
+        name: String,
+    },
+    /// `name = value;` in the `let` block.
+    Variable {
+        name: String,
+        value: Box<NixValue>,
+    },
+    Lambda {
+        param: Param,
+        body: Box<NixValue>,
+    },
+    /// Binding names that are not identifiers are quoted.
+    Let {
+        bindings: Vec<(String, NixValue)>,
+        body: Box<NixValue>,
+    },
+    With {
+        namespace: Box<NixValue>,
+        body: Box<NixValue>,
+    },
+    Select {
+        value: Box<NixValue>,
+        path: Vec<String>,
+    },
+    Apply {
+        function: Box<NixValue>,
+        argument: Box<NixValue>,
+    },
+    /// Tokens built elsewhere, such as with `quote!`.
+    Tokens(nix::Tokens),
+}
+
+impl NixValue {
+    pub fn argument(name: &str) -> Self {
+        NixValue::Argument(String::from(name))
+    }
+
+    pub fn inherit(path: &str, name: &str) -> Self {
+        NixValue::Inherit {
+            path: String::from(path),
+            name: String::from(name),
+        }
+    }
+
+    /// Select the dotted attribute `path` of `value`, e.g. `nixosModules.disko`.
+    pub fn select(value: NixValue, path: &str) -> Self {
+        Self::select1(value, path.split('.'))
+    }
+
+    /// Select an attribute path given as separate names, for names holding a
+    /// dot themselves.
+    pub fn select1<'a, P>(value: NixValue, path: P) -> Self
+    where
+        P: IntoIterator<Item = &'a str>,
+    {
+        let path: Vec<String> = path.into_iter().map(String::from).collect();
+        if path.is_empty() {
+            return value;
+        }
+        NixValue::Select {
+            value: Box::new(value),
+            path,
+        }
+    }
+
+    pub fn apply(function: NixValue, argument: NixValue) -> Self {
+        NixValue::Apply {
+            function: Box::new(function),
+            argument: Box::new(argument),
+        }
+    }
+
+    /// Whether the value can be applied or selected from without parentheses.
+    fn is_atom(&self) -> bool {
+        !matches!(
+            self,
+            NixValue::Lambda { .. }
+                | NixValue::Let { .. }
+                | NixValue::With { .. }
+                | NixValue::Apply { .. }
+        ) && !matches!(self, NixValue::Int(value) if *value < 0)
+            && !matches!(self, NixValue::Float(value) if value.is_finite() && value.is_sign_negative())
+    }
+}
+
+/// Escape a literal part of a string. `${` is escaped, and so is a trailing
+/// `$` when an interpolation follows.
+fn escape(literal: &str, interpolation_follows: bool, out: &mut String) {
+    let mut chars = literal.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '$' if chars.peek() == Some(&'{') => out.push_str("\\$"),
+            '$' if chars.peek().is_none() && interpolation_follows => out.push_str("\\$"),
+            c => out.push(c),
+        }
+    }
+}
+
+/// Nix floats need a fraction, `1.0e20` rather than `1e20`. Nix has no
+/// literal for infinity and NaN, so they are written as an overflowing
+/// product.
+fn float(value: f64) -> String {
+    if value.is_nan() {
+        return String::from("(1.0e308 * 10 - 1.0e308 * 10)");
+    }
+    if value.is_infinite() {
+        let sign = if value < 0.0 { "-" } else { "" };
+        return format!("({}1.0e308 * 10)", sign);
+    }
+    let float = format!("{:?}", value);
+    match float.split_once('e') {
+        Some((mantissa, exponent)) if !mantissa.contains('.') => {
+            format!("{}.0e{}", mantissa, exponent)
+        }
+        _ => float,
+    }
+}
+
+/// Append `value`, in parentheses unless it is an atom.
+pub(crate) fn atom(value: NixValue, tokens: &mut Tokens<Nix>) {
+    if value.is_atom() {
+        tokens.append(value);
+    } else {
+        quote_in!(*tokens => ($value));
+    }
+}
+
+fn attr_path(path: Vec<String>, tokens: &mut Tokens<Nix>) {
+    for (i, name) in path.iter().enumerate() {
+        if i > 0 {
+            tokens.append(".");
+        }
+        tokens.append(attr_name(name));
+    }
+}
+
+fn bindings(bindings: Vec<(Vec<String>, NixValue)>, tokens: &mut Tokens<Nix>) {
+    tokens.append("{");
+    tokens.indent();
+    for (path, value) in bindings {
+        attr_path(path, tokens);
+        quote_in!(*tokens => $[' ']= $value;);
+        tokens.push();
+    }
+    tokens.unindent();
+    tokens.append("}");
+}
+
+impl FormatInto<Nix> for NixValue {
+    fn format_into(self, tokens: &mut Tokens<Nix>) {
+        match self {
+            NixValue::Null => tokens.append("null"),
+            NixValue::Bool(value) => tokens.append(if value { "true" } else { "false" }),
+            NixValue::Int(value) => tokens.append(value.to_string()),
+            NixValue::Float(value) => tokens.append(float(value)),
+            NixValue::String(parts) => {
+                let mut literal = String::from("\"");
+                let mut parts = parts.into_iter().peekable();
+                while let Some(part) = parts.next() {
+                    match part {
+                        StringPart::Literal(value) => {
+                            let interpolation_follows =
+                                matches!(parts.peek(), Some(StringPart::Interpolation(_)));
+                            escape(&value, interpolation_follows, &mut literal);
+                        }
+                        StringPart::Interpolation(value) => {
+                            literal.push_str("${");
+                            tokens.append(std::mem::take(&mut literal));
+                            tokens.append(value);
+                            literal.push('}');
+                        }
+                    }
+                }
+                literal.push('"');
+                tokens.append(literal);
+            }
+            NixValue::Path(path) => tokens.append(path),
+            NixValue::List(values) => {
+                tokens.append("[");
+                tokens.indent();
+                for value in values {
+                    atom(value, tokens);
+                    tokens.push();
+                }
+                tokens.unindent();
+                tokens.append("]");
+            }
+            NixValue::AttrSet(attrs) => bindings(attrs, tokens),
+            NixValue::RecAttrSet(attrs) => {
+                tokens.append("rec");
+                tokens.space();
+                bindings(attrs, tokens);
+            }
+            NixValue::Ident(name) => tokens.append(name),
+            NixValue::Argument(name) => tokens.append(nix::argument(name)),
+            NixValue::Inherit { path, name } => tokens.append(nix::inherit(path, name)),
+            NixValue::Variable { name, value } => {
+                // Inherits of the value go to the outer tokens, where
+                // `inherit::merge` can see them.
+                let inherited = Inherited::of(&*value);
+                let hoisted = inherit::hoist(&quote!($(*value)), &inherited.inherits);
+                for (path, name) in hoisted.inherits {
+                    tokens.register(nix::inherit(path, name));
+                }
+                tokens.append(nix::variable(name, hoisted.tokens))
+            }
+            NixValue::Lambda { param, body } => {
+                match param {
+                    Param::Ident(name) => tokens.append(name),
+                    Param::Pattern { formals, ellipsis } => {
+                        let mut names = formals;
+                        if ellipsis {
+                            names.push(String::from("..."));
+                        }
+                        quote_in!(*tokens => { $(for name in names join (, ) => $name) });
+                    }
+                }
+                quote_in!(*tokens => : $(*body));
+            }
+            NixValue::Let { bindings, body } => {
+                tokens.append("let");
+                tokens.indent();
+                for (name, value) in bindings {
+                    quote_in!(*tokens => $(attr_name(&name)) = $value;);
+                    tokens.push();
+                }
+                tokens.unindent();
+                tokens.append("in");
+                tokens.space();
+                tokens.append(*body);
+            }
+            NixValue::With { namespace, body } => {
+                quote_in!(*tokens => with $(*namespace); $(*body));
+            }
+            NixValue::Select { value, path } => {
+                atom(*value, tokens);
+                tokens.append(".");
+                attr_path(path, tokens);
+            }
+            NixValue::Apply { function, argument } => {
+                match *function {
+                    function @ NixValue::Apply { .. } => tokens.append(function),
+                    function => atom(function, tokens),
+                }
+                tokens.space();
+                atom(*argument, tokens);
+            }
+            NixValue::Tokens(value) => tokens.append(value),
+        }
+    }
+}
+
+impl From<bool> for NixValue {
+    fn from(value: bool) -> Self {
+        NixValue::Bool(value)
+    }
+}
+
+impl From<i64> for NixValue {
+    fn from(value: i64) -> Self {
+        NixValue::Int(value)
+    }
+}
+
+impl From<f64> for NixValue {
+    fn from(value: f64) -> Self {
+        NixValue::Float(value)
+    }
+}
+
+impl From<&str> for NixValue {
+    fn from(value: &str) -> Self {
+        NixValue::from(String::from(value))
+    }
+}
+
+impl From<String> for NixValue {
+    fn from(value: String) -> Self {
+        NixValue::String(vec![StringPart::Literal(value)])
+    }
+}
+
+impl<T> From<Vec<T>> for NixValue
+where
+    T: Into<NixValue>,
+{
+    fn from(values: Vec<T>) -> Self {
+        NixValue::List(values.into_iter().map(Into::into).collect())
+    }
+}
+
+impl From<nix::Tokens> for NixValue {
+    fn from(tokens: nix::Tokens) -> Self {
+        NixValue::Tokens(tokens)
+    }
+}
+
+impl From<NixValue> for nix::Tokens {
+    fn from(value: NixValue) -> Self {
+        quote!($value)
+    }
+}